@@ -0,0 +1,109 @@
+//! Aggregates over the entries of a DB or DBView
+
+use std::iter::Sum;
+
+use {DB, DBView};
+
+/// A user-defined aggregate, such as a count or a histogram, which is fed entries one at a time
+/// and then produces a single result
+pub trait Aggregator<T> {
+    /// The result of the aggregate
+    type Output;
+
+    /// Adds `entry` to the aggregate
+    fn accumulate(&mut self, entry: &T);
+
+    /// Produces the result of the aggregate from everything accumulated so far
+    fn finish(self) -> Self::Output;
+}
+
+fn aggregate<'a, T: 'a, A, I>(entries: I, mut aggregator: A) -> A::Output
+    where A: Aggregator<T>,
+          I: Iterator<Item = &'a T>
+{
+    for entry in entries {
+        aggregator.accumulate(entry);
+    }
+    aggregator.finish()
+}
+
+fn mean<'a, T: 'a, F, I>(entries: I, f: F) -> Option<f64>
+    where F: Fn(&T) -> f64,
+          I: Iterator<Item = &'a T>
+{
+    let (count, total) = entries.fold((0usize, 0.0), |(count, total), t| (count + 1, total + f(t)));
+    if count == 0 {
+        None
+    } else {
+        Some(total / count as f64)
+    }
+}
+
+// `DB` and `DBView` expose exactly the same aggregates, differing only in where the entries come
+// from and how long the returned references live.
+macro_rules! aggregate_methods {
+    ($lt:lifetime) => {
+        /// Returns the number of entries in `self` which satisfy `predicate`
+        pub fn count_where<F>(&self, predicate: F) -> usize
+            where F: Fn(&T) -> bool
+        {
+            self.iter().filter(|t| predicate(t)).count()
+        }
+
+        /// Returns the sum of `f` over the entries of `self`
+        pub fn sum_by<S, F>(&self, f: F) -> S
+            where S: Sum<S>,
+                  F: Fn(&T) -> S
+        {
+            self.iter().map(f).sum()
+        }
+
+        /// Returns the entry of `self` with the smallest `key`, or `None` if `self` is empty. If
+        /// several entries are equally small, the first is returned.
+        pub fn min_by_key<K, F>(&self, key: F) -> Option<&$lt T>
+            where K: Ord,
+                  F: Fn(&T) -> K
+        {
+            self.iter().min_by_key(|t| key(t))
+        }
+
+        /// Returns the entry of `self` with the largest `key`, or `None` if `self` is empty. If
+        /// several entries are equally large, the last is returned.
+        pub fn max_by_key<K, F>(&self, key: F) -> Option<&$lt T>
+            where K: Ord,
+                  F: Fn(&T) -> K
+        {
+            self.iter().max_by_key(|t| key(t))
+        }
+
+        /// Returns the arithmetic mean of `f` over the entries of `self`, or `None` if `self` is
+        /// empty
+        pub fn mean_by<F>(&self, f: F) -> Option<f64>
+            where F: Fn(&T) -> f64
+        {
+            mean(self.iter(), f)
+        }
+
+        /// Folds every entry of `self` into an accumulator, starting from `init`
+        pub fn fold<B, F>(&self, init: B, f: F) -> B
+            where F: FnMut(B, &T) -> B
+        {
+            self.iter().fold(init, f)
+        }
+
+        /// Feeds every entry of `self` into `aggregator` and returns its result
+        pub fn aggregate<A>(&self, aggregator: A) -> A::Output
+            where A: Aggregator<T>
+        {
+            aggregate(self.iter(), aggregator)
+        }
+    }
+}
+
+impl<T> DB<T> {
+    aggregate_methods!('_);
+}
+
+impl<'a, T> DBView<'a, T> {
+    aggregate_methods!('a);
+}
@@ -0,0 +1,384 @@
+//! A compact binary file format for DBs of records
+//!
+//! All integers are little-endian. A file is laid out as:
+//!
+//! - the magic number `b"wk3\0"`
+//! - the format version, as a `u16`
+//! - the fingerprint of the record type's schema, as a `u64`
+//! - the DB's next id, as a `u64`, so that ids aren't reused after loading
+//! - the number of rows, as a `u64`
+//! - each row: its length in bytes as a `u32`, then its id as a `u64`, then its values
+//! - a CRC-32 checksum of everything before it, as a `u32`
+//!
+//! Each value is a tag byte followed by its contents: nothing for `NULL` (0), a byte for a bool
+//! (1), 8 bytes for an int (2) or float (3), and a `u32` length or count followed by the contents
+//! for a string (4), bytes (5) or list (6).
+
+use std::convert::TryInto;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+
+use {DB, FieldType, Record, RowId, Schema, TypeError, Value};
+
+const MAGIC: &[u8; 4] = b"wk3\0";
+const VERSION: u16 = 1;
+/// How deeply lists can be nested in a file which is loaded
+const MAX_DEPTH: usize = 64;
+
+/// Why a binary file couldn't be loaded
+#[derive(Debug)]
+pub enum BinaryError {
+    Io(io::Error),
+    /// The file doesn't start with the magic number, so isn't a binary DB
+    BadMagic,
+    /// The file is in a version of the format this version of the crate can't read
+    UnsupportedVersion(u16),
+    /// The file holds records of a different type, whose schema has the fingerprint `found`
+    SchemaMismatch { expected: u64, found: u64 },
+    /// The file ended early
+    Truncated,
+    /// The row at this position in the file, counting from 0, couldn't be decoded
+    CorruptRow(u64),
+    /// The values of the row at position `row` don't make a record
+    Type { row: u64, error: TypeError },
+    /// The checksum at the end of the file doesn't match its contents
+    ChecksumMismatch { expected: u32, found: u32 },
+    /// There is more data after the checksum
+    TrailingData,
+}
+
+impl fmt::Display for BinaryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BinaryError::Io(ref e) => write!(f, "{}", e),
+            BinaryError::BadMagic => write!(f, "not a wk3 binary file"),
+            BinaryError::UnsupportedVersion(v) => write!(f, "unsupported format version {}", v),
+            BinaryError::SchemaMismatch { expected, found } => {
+                write!(f, "schema fingerprint is {:016x}, expected {:016x}", found, expected)
+            }
+            BinaryError::Truncated => write!(f, "file is truncated"),
+            BinaryError::CorruptRow(row) => write!(f, "row {} is corrupt", row),
+            BinaryError::Type { row, ref error } => write!(f, "row {}: {}", row, error),
+            BinaryError::ChecksumMismatch { expected, found } => {
+                write!(f, "checksum is {:08x}, expected {:08x}", found, expected)
+            }
+            BinaryError::TrailingData => write!(f, "data after the checksum"),
+        }
+    }
+}
+
+impl Error for BinaryError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            BinaryError::Io(ref e) => Some(e),
+            BinaryError::Type { ref error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for BinaryError {
+    fn from(e: io::Error) -> BinaryError {
+        if e.kind() == io::ErrorKind::UnexpectedEof {
+            BinaryError::Truncated
+        } else {
+            BinaryError::Io(e)
+        }
+    }
+}
+
+impl Schema {
+    /// Returns a hash of the names, types and nullability of the fields, in order. It is the
+    /// same in every version of the crate, so it can be saved to tell schemas apart later.
+    pub fn fingerprint(&self) -> u64 {
+        // 64-bit FNV-1a
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut feed = |bytes: &[u8]| {
+            for &b in bytes {
+                hash = (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3);
+            }
+        };
+        for field in self.fields() {
+            feed(&(field.name.len() as u64).to_le_bytes());
+            feed(field.name.as_bytes());
+            let ty = match field.ty {
+                FieldType::Bool => 1,
+                FieldType::Int => 2,
+                FieldType::Float => 3,
+                FieldType::Str => 4,
+                FieldType::Bytes => 5,
+                FieldType::List => 6,
+            };
+            feed(&[ty, field.nullable as u8]);
+        }
+        hash
+    }
+}
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+/// Wraps a reader or writer, keeping a CRC-32 of the bytes which pass through it
+struct Crc<S> {
+    inner: S,
+    crc: u32,
+}
+
+impl<S> Crc<S> {
+    fn new(inner: S) -> Crc<S> {
+        Crc { inner, crc: !0 }
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.crc = CRC_TABLE[((self.crc ^ b as u32) & 0xff) as usize] ^ (self.crc >> 8);
+        }
+    }
+
+    fn sum(&self) -> u32 {
+        !self.crc
+    }
+}
+
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = Crc::new(());
+    crc.update(bytes);
+    crc.sum()
+}
+
+impl<W: Write> Write for Crc<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<R: Read> Read for Crc<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+fn read_array<R: Read, const N: usize>(input: &mut R) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    input.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+pub(crate) fn encode_value(buf: &mut Vec<u8>, value: &Value) {
+    match *value {
+        Value::Null => buf.push(0),
+        Value::Bool(b) => buf.extend_from_slice(&[1, b as u8]),
+        Value::Int(i) => {
+            buf.push(2);
+            buf.extend_from_slice(&i.to_le_bytes());
+        }
+        Value::Float(x) => {
+            buf.push(3);
+            buf.extend_from_slice(&x.to_bits().to_le_bytes());
+        }
+        Value::Str(ref s) => {
+            buf.push(4);
+            encode_len(buf, s.len());
+            buf.extend_from_slice(s.as_bytes());
+        }
+        Value::Bytes(ref bytes) => {
+            buf.push(5);
+            encode_len(buf, bytes.len());
+            buf.extend_from_slice(bytes);
+        }
+        Value::List(ref values) => {
+            buf.push(6);
+            encode_len(buf, values.len());
+            for value in values {
+                encode_value(buf, value);
+            }
+        }
+    }
+}
+
+fn encode_len(buf: &mut Vec<u8>, len: usize) {
+    buf.extend_from_slice(&(len as u32).to_le_bytes());
+}
+
+/// Reads values back out of the bytes of a row
+pub(crate) struct Decoder<'b> {
+    bytes: &'b [u8],
+}
+
+impl<'b> Decoder<'b> {
+    pub(crate) fn new(bytes: &'b [u8]) -> Decoder<'b> {
+        Decoder { bytes }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    fn take(&mut self, n: usize) -> Option<&'b [u8]> {
+        if n > self.bytes.len() {
+            return None;
+        }
+        let (taken, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Some(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> Option<[u8; N]> {
+        self.take(N).map(|bytes| bytes.try_into().expect("took N bytes"))
+    }
+
+    pub(crate) fn byte(&mut self) -> Option<u8> {
+        self.array::<1>().map(|[b]| b)
+    }
+
+    pub(crate) fn u64(&mut self) -> Option<u64> {
+        self.array().map(u64::from_le_bytes)
+    }
+
+    fn len(&mut self) -> Option<usize> {
+        self.array().map(|bytes| u32::from_le_bytes(bytes) as usize)
+    }
+
+    pub(crate) fn value(&mut self) -> Option<Value> {
+        self.value_within(MAX_DEPTH)
+    }
+
+    fn value_within(&mut self, depth: usize) -> Option<Value> {
+        let value = match self.byte()? {
+            0 => Value::Null,
+            1 => match self.byte()? {
+                0 => Value::Bool(false),
+                1 => Value::Bool(true),
+                _ => return None,
+            },
+            2 => Value::Int(i64::from_le_bytes(self.array()?)),
+            3 => Value::Float(f64::from_bits(u64::from_le_bytes(self.array()?))),
+            4 => {
+                let len = self.len()?;
+                Value::Str(String::from_utf8(self.take(len)?.to_vec()).ok()?)
+            }
+            5 => {
+                let len = self.len()?;
+                Value::Bytes(self.take(len)?.to_vec())
+            }
+            6 if depth > 0 => {
+                let count = self.len()?;
+                // Every value takes at least a byte, which bounds how much to allocate up front
+                let mut values = Vec::with_capacity(count.min(self.bytes.len()));
+                for _ in 0..count {
+                    values.push(self.value_within(depth - 1)?);
+                }
+                Value::List(values)
+            }
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+impl<T: Record> DB<T> {
+    /// Saves every entry of `self`, along with its id, in the binary format
+    pub fn save_binary<W: Write>(&self, output: W) -> io::Result<()> {
+        let mut output = Crc::new(BufWriter::new(output));
+        output.write_all(MAGIC)?;
+        output.write_all(&VERSION.to_le_bytes())?;
+        output.write_all(&T::schema().fingerprint().to_le_bytes())?;
+        output.write_all(&self.next_id.to_le_bytes())?;
+        output.write_all(&(self.len() as u64).to_le_bytes())?;
+        let mut row = Vec::new();
+        for (id, entry) in self.rows() {
+            row.clear();
+            row.extend_from_slice(&id.0.to_le_bytes());
+            for value in entry.values() {
+                encode_value(&mut row, &value);
+            }
+            if row.len() > u32::MAX as usize {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, "row is too big to save"));
+            }
+            output.write_all(&(row.len() as u32).to_le_bytes())?;
+            output.write_all(&row)?;
+        }
+        let sum = output.sum();
+        output.write_all(&sum.to_le_bytes())?;
+        output.flush()
+    }
+
+    /// Loads a DB saved by `save_binary`, with the same ids it had. The file must hold records
+    /// of type `T`, and fails to load if it has been corrupted or cut short.
+    pub fn load_binary<R: Read>(input: R) -> Result<DB<T>, BinaryError> {
+        let mut input = Crc::new(BufReader::new(input));
+        if read_array(&mut input)? != *MAGIC {
+            return Err(BinaryError::BadMagic);
+        }
+        let version = u16::from_le_bytes(read_array(&mut input)?);
+        if version != VERSION {
+            return Err(BinaryError::UnsupportedVersion(version));
+        }
+        let expected = T::schema().fingerprint();
+        let found = u64::from_le_bytes(read_array(&mut input)?);
+        if found != expected {
+            return Err(BinaryError::SchemaMismatch { expected, found });
+        }
+        let next_id = u64::from_le_bytes(read_array(&mut input)?);
+        let count = u64::from_le_bytes(read_array(&mut input)?);
+
+        let mut ids = Vec::new();
+        let mut data = Vec::new();
+        let mut row = Vec::new();
+        for i in 0..count {
+            let len = u32::from_le_bytes(read_array(&mut input)?) as usize;
+            row.clear();
+            // Reading through `take` only allocates as much as is really there, whatever `len` says
+            (&mut input).take(len as u64).read_to_end(&mut row)?;
+            if row.len() < len {
+                return Err(BinaryError::Truncated);
+            }
+            let mut decoder = Decoder::new(&row);
+            let id = decoder.u64().ok_or(BinaryError::CorruptRow(i))?;
+            if id >= next_id || ids.last().is_some_and(|&RowId(last)| id <= last) {
+                return Err(BinaryError::CorruptRow(i));
+            }
+            let mut values = Vec::new();
+            while !decoder.is_empty() {
+                values.push(decoder.value().ok_or(BinaryError::CorruptRow(i))?);
+            }
+            let entry = T::from_values(values);
+            data.push(entry.map_err(|error| BinaryError::Type { row: i, error })?);
+            ids.push(RowId(id));
+        }
+
+        let expected = input.sum();
+        let found = u32::from_le_bytes(read_array(&mut input)?);
+        if found != expected {
+            return Err(BinaryError::ChecksumMismatch { expected, found });
+        }
+        if input.read(&mut [0])? > 0 {
+            return Err(BinaryError::TrailingData);
+        }
+        Ok(DB::from_rows(next_id, ids, data))
+    }
+}
@@ -0,0 +1,408 @@
+//! Reading and writing records as CSV
+//!
+//! Each line holds one record, with its fields in the order of the schema or, if there is a
+//! header line, in the order the header names them. A field is quoted with `"` if it contains
+//! the delimiter, a quote or a line break, and quotes inside it are doubled. An empty unquoted
+//! field is `NULL`, while `""` is an empty string or empty bytes. Booleans are written as `true`
+//! and `false`, and bytes in hex. List fields can't be read or written. Blank lines are skipped,
+//! except with only one column, where a blank line is a row whose field is `NULL`.
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+use {DB, DBView, Field, FieldType, Record, TypeError, Value};
+
+/// How CSV is laid out. The default has a header line and separates fields with commas.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CsvFormat {
+    delimiter: char,
+    headers: bool,
+}
+
+impl Default for CsvFormat {
+    fn default() -> CsvFormat {
+        CsvFormat { delimiter: ',', headers: true }
+    }
+}
+
+impl CsvFormat {
+    /// Returns the default format
+    pub fn new() -> CsvFormat {
+        CsvFormat::default()
+    }
+
+    /// Separates fields with `delimiter`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `delimiter` is a quote or a line break.
+    pub fn delimiter(self, delimiter: char) -> CsvFormat {
+        assert!(!matches!(delimiter, '"' | '\r' | '\n'), "invalid CSV delimiter {:?}", delimiter);
+        CsvFormat { delimiter, ..self }
+    }
+
+    /// Sets whether the first line names the fields. Without one, fields are in schema order.
+    pub fn headers(self, headers: bool) -> CsvFormat {
+        CsvFormat { headers, ..self }
+    }
+}
+
+/// Something that went wrong reading or writing CSV, and the line it went wrong on
+#[derive(Debug)]
+pub struct CsvError {
+    line: usize,
+    kind: CsvErrorKind,
+}
+
+#[derive(Debug)]
+pub enum CsvErrorKind {
+    Io(io::Error),
+    /// A quoted field wasn't closed before the end of the input
+    UnclosedQuote,
+    /// A quoted field was followed by something other than a delimiter or the end of the line
+    TextAfterQuote,
+    /// The header named a field the record type doesn't have
+    UnknownColumn(String),
+    /// The header named the same field twice
+    DuplicateColumn(String),
+    /// The header didn't name this field of the record type
+    MissingColumn(String),
+    /// A row had `found` fields, rather than the `expected` number
+    WrongLength { expected: usize, found: usize },
+    /// `text` isn't a valid value for the field called `field`
+    BadValue { field: String, text: String },
+    /// The values of a row don't make a record
+    Type(TypeError),
+    /// The field called `field` holds lists, which CSV can't hold
+    Unsupported { field: String },
+}
+
+impl CsvError {
+    fn new(line: usize, kind: CsvErrorKind) -> CsvError {
+        CsvError { line, kind }
+    }
+
+    /// Returns the line of the input or output the error happened on, counting from 1. A row
+    /// which spans several lines is counted as being on its first.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Returns what went wrong
+    pub fn kind(&self) -> &CsvErrorKind {
+        &self.kind
+    }
+
+    /// Returns what went wrong, consuming the error
+    pub fn into_kind(self) -> CsvErrorKind {
+        self.kind
+    }
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            CsvErrorKind::Io(ref e) => write!(f, "{}", e)?,
+            CsvErrorKind::UnclosedQuote => write!(f, "unclosed quote")?,
+            CsvErrorKind::TextAfterQuote => write!(f, "text after closing quote")?,
+            CsvErrorKind::UnknownColumn(ref name) => write!(f, "unknown column `{}`", name)?,
+            CsvErrorKind::DuplicateColumn(ref name) => write!(f, "duplicate column `{}`", name)?,
+            CsvErrorKind::MissingColumn(ref name) => write!(f, "missing column `{}`", name)?,
+            CsvErrorKind::WrongLength { expected, found } => {
+                write!(f, "expected {} fields, found {}", expected, found)?
+            }
+            CsvErrorKind::BadValue { ref field, ref text } => {
+                write!(f, "invalid value {:?} for field `{}`", text, field)?
+            }
+            CsvErrorKind::Type(ref e) => write!(f, "{}", e)?,
+            CsvErrorKind::Unsupported { ref field } => {
+                write!(f, "field `{}` holds lists, which CSV can't hold", field)?
+            }
+        }
+        write!(f, " at line {}", self.line)
+    }
+}
+
+impl Error for CsvError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self.kind {
+            CsvErrorKind::Io(ref e) => Some(e),
+            CsvErrorKind::Type(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// One field of a row as it was read, and whether it was quoted
+struct RawField {
+    text: String,
+    quoted: bool,
+}
+
+/// Splits its input into rows of fields
+struct Reader<R> {
+    input: R,
+    delimiter: char,
+    /// The number of lines read so far
+    line: usize,
+    /// Whether blank lines are skipped, rather than read as a row with one empty field
+    skip_blank: bool,
+}
+
+impl<R: BufRead> Reader<R> {
+    /// Reads the next row, skipping blank lines if `skip_blank` is set, returning it with the line
+    /// it started on
+    fn next_row(&mut self) -> Result<Option<(usize, Vec<RawField>)>, CsvError> {
+        loop {
+            let mut text = String::new();
+            if !self.read_line(&mut text)? {
+                return Ok(None);
+            }
+            if !self.skip_blank || !trim_newline(&text).is_empty() {
+                let start = self.line;
+                return self.split(start, text).map(|fields| Some((start, fields)));
+            }
+        }
+    }
+
+    /// Reads a line onto `text`, returning false at the end of the input
+    fn read_line(&mut self, text: &mut String) -> Result<bool, CsvError> {
+        let read = self.input.read_line(text);
+        let read = read.map_err(|e| CsvError::new(self.line + 1, CsvErrorKind::Io(e)))?;
+        if read > 0 {
+            self.line += 1;
+        }
+        Ok(read > 0)
+    }
+
+    /// Splits the row starting with the line `text` into fields, reading more lines if a quoted
+    /// field has a line break in it
+    fn split(&mut self, start: usize, mut text: String) -> Result<Vec<RawField>, CsvError> {
+        let mut fields = Vec::new();
+        let mut pos = 0;
+        loop {
+            let mut field = RawField { text: String::new(), quoted: false };
+            if text[pos..].starts_with('"') {
+                field.quoted = true;
+                pos += 1;
+                loop {
+                    match text[pos..].find('"') {
+                        Some(i) => {
+                            field.text.push_str(&text[pos..pos + i]);
+                            pos += i + 1;
+                            if !text[pos..].starts_with('"') {
+                                break;
+                            }
+                            field.text.push('"');
+                            pos += 1;
+                        }
+                        None => {
+                            field.text.push_str(&text[pos..]);
+                            pos = text.len();
+                            if !self.read_line(&mut text)? {
+                                return Err(CsvError::new(start, CsvErrorKind::UnclosedQuote));
+                            }
+                        }
+                    }
+                }
+            }
+            let rest = trim_newline(&text[pos..]);
+            let end = rest.find(self.delimiter).unwrap_or(rest.len());
+            if field.quoted && end > 0 {
+                return Err(CsvError::new(self.line, CsvErrorKind::TextAfterQuote));
+            }
+            field.text.push_str(&rest[..end]);
+            fields.push(field);
+            if end == rest.len() {
+                return Ok(fields);
+            }
+            pos += end + self.delimiter.len_utf8();
+        }
+    }
+}
+
+fn trim_newline(text: &str) -> &str {
+    let text = text.strip_suffix('\n').unwrap_or(text);
+    text.strip_suffix('\r').unwrap_or(text)
+}
+
+/// Parses the text of a field of a row starting at `line`
+fn parse(line: usize, field: &Field, raw: RawField) -> Result<Value, CsvError> {
+    let can_be_empty = matches!(field.ty, FieldType::Str | FieldType::Bytes);
+    if raw.text.is_empty() && !raw.quoted && (field.nullable || !can_be_empty) {
+        return Ok(Value::Null);
+    }
+    let bad_value = || {
+        let kind = CsvErrorKind::BadValue { field: field.name.clone(), text: raw.text.clone() };
+        CsvError::new(line, kind)
+    };
+    let value = match field.ty {
+        FieldType::Bool if raw.text.eq_ignore_ascii_case("true") => Value::Bool(true),
+        FieldType::Bool if raw.text.eq_ignore_ascii_case("false") => Value::Bool(false),
+        FieldType::Bool => return Err(bad_value()),
+        FieldType::Int => Value::Int(raw.text.trim().parse().map_err(|_| bad_value())?),
+        FieldType::Float => Value::Float(raw.text.trim().parse().map_err(|_| bad_value())?),
+        FieldType::Str => Value::Str(raw.text),
+        FieldType::Bytes => Value::Bytes(from_hex(&raw.text).ok_or_else(bad_value)?),
+        FieldType::List => {
+            let kind = CsvErrorKind::Unsupported { field: field.name.clone() };
+            return Err(CsvError::new(line, kind));
+        }
+    };
+    Ok(value)
+}
+
+pub(crate) fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) || !text.is_ascii() {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Returns the text of `value` as it is written in a field, or `None` for a list
+fn format(value: &Value) -> Option<String> {
+    let text = match *value {
+        Value::Null => String::new(),
+        Value::Bool(b) => b.to_string(),
+        Value::Int(i) => i.to_string(),
+        Value::Float(x) => x.to_string(),
+        Value::Str(ref s) => s.clone(),
+        Value::Bytes(ref bytes) => bytes.iter().map(|b| format!("{:02x}", b)).collect(),
+        Value::List(_) => return None,
+    };
+    Some(text)
+}
+
+impl CsvFormat {
+    /// Reads records from `input` into a new DB
+    pub fn read<T: Record, R: Read>(&self, input: R) -> Result<DB<T>, CsvError> {
+        let schema = T::schema();
+        let input = BufReader::new(input);
+        let mut reader = Reader { input, delimiter: self.delimiter, line: 0, skip_blank: true };
+        // The position in the schema of the field in each column
+        let mut columns: Vec<usize> = (0..schema.len()).collect();
+        if self.headers {
+            let (line, header) = match reader.next_row()? {
+                Some(header) => header,
+                None => return Ok(DB::new(Vec::new())),
+            };
+            columns.clear();
+            for name in header.into_iter().map(|f| f.text) {
+                let i = match schema.position(&name) {
+                    Some(i) => i,
+                    None => return Err(CsvError::new(line, CsvErrorKind::UnknownColumn(name))),
+                };
+                if columns.contains(&i) {
+                    return Err(CsvError::new(line, CsvErrorKind::DuplicateColumn(name)));
+                }
+                columns.push(i);
+            }
+            let missing = schema.fields().iter().enumerate().find(|&(i, _)| !columns.contains(&i));
+            if let Some((_, field)) = missing {
+                let kind = CsvErrorKind::MissingColumn(field.name.clone());
+                return Err(CsvError::new(line, kind));
+            }
+        }
+
+        // With one column, a row whose field is `NULL` is written as a blank line
+        reader.skip_blank = columns.len() != 1;
+        let mut db = DB::new(Vec::new());
+        while let Some((line, row)) = reader.next_row()? {
+            if row.len() != columns.len() {
+                let kind = CsvErrorKind::WrongLength { expected: columns.len(), found: row.len() };
+                return Err(CsvError::new(line, kind));
+            }
+            let mut values = vec![Value::Null; schema.len()];
+            for (&i, raw) in columns.iter().zip(row) {
+                values[i] = parse(line, &schema.fields()[i], raw)?;
+            }
+            let record = T::from_values(values);
+            db.insert(record.map_err(|e| CsvError::new(line, CsvErrorKind::Type(e)))?);
+        }
+        Ok(db)
+    }
+
+    /// Writes the records in `view` to `output`, in order
+    pub fn write<T: Record, W: Write>(&self, view: &DBView<T>, output: W) -> Result<(), CsvError> {
+        let schema = T::schema();
+        let mut writer = Writer { output, delimiter: self.delimiter, line: 0 };
+        if self.headers {
+            writer.write_row(schema.names().map(|name| (name.to_string(), false)))?;
+        }
+        for entry in view {
+            let mut fields = Vec::with_capacity(schema.len());
+            for (field, value) in schema.fields().iter().zip(entry.values()) {
+                let text = format(&value).ok_or_else(|| {
+                    let kind = CsvErrorKind::Unsupported { field: field.name.clone() };
+                    CsvError::new(writer.line + 1, kind)
+                })?;
+                // Empty strings and bytes are quoted so they aren't read back as `NULL`
+                let quote = text.is_empty() && !value.is_null();
+                fields.push((text, quote));
+            }
+            writer.write_row(fields)?;
+        }
+        writer.output.flush().map_err(|e| CsvError::new(writer.line, CsvErrorKind::Io(e)))
+    }
+}
+
+struct Writer<W> {
+    output: W,
+    delimiter: char,
+    /// The number of lines written so far
+    line: usize,
+}
+
+impl<W: Write> Writer<W> {
+    /// Writes a row of fields, each with whether it has to be quoted regardless of its text
+    fn write_row<I>(&mut self, fields: I) -> Result<(), CsvError>
+        where I: IntoIterator<Item = (String, bool)>
+    {
+        let mut row = String::new();
+        for (i, (text, quote)) in fields.into_iter().enumerate() {
+            if i > 0 {
+                row.push(self.delimiter);
+            }
+            let special = |c: char| c == self.delimiter || matches!(c, '"' | '\r' | '\n');
+            if quote || text.contains(special) {
+                row.push('"');
+                row.push_str(&text.replace('"', "\"\""));
+                row.push('"');
+            } else {
+                row.push_str(&text);
+            }
+        }
+        row.push('\n');
+        let start = self.line + 1;
+        self.line += row.matches('\n').count();
+        self.output.write_all(row.as_bytes()).map_err(|e| CsvError::new(start, CsvErrorKind::Io(e)))
+    }
+}
+
+impl<T: Record> DB<T> {
+    /// Reads records from CSV with a header line and commas between fields
+    pub fn from_csv<R: Read>(input: R) -> Result<DB<T>, CsvError> {
+        CsvFormat::new().read(input)
+    }
+
+    /// Reads records from CSV laid out as `format` says
+    pub fn from_csv_with<R: Read>(input: R, format: &CsvFormat) -> Result<DB<T>, CsvError> {
+        format.read(input)
+    }
+}
+
+impl<'a, T: Record> DBView<'a, T> {
+    /// Writes the records of `self` as CSV with a header line and commas between fields
+    pub fn to_csv<W: Write>(&self, output: W) -> Result<(), CsvError> {
+        CsvFormat::new().write(self, output)
+    }
+
+    /// Writes the records of `self` as CSV laid out as `format` says
+    pub fn to_csv_with<W: Write>(&self, output: W, format: &CsvFormat) -> Result<(), CsvError> {
+        format.write(self, output)
+    }
+}
@@ -0,0 +1,137 @@
+//! Plans describing how a query will be run, as returned by `Query::explain`
+
+use std::fmt;
+use std::time::Duration;
+
+/// One step of a query plan, together with the step feeding it entries. A plan is read from the
+/// innermost step, which finds the candidate entries, outwards to the root, which produces the
+/// results.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Plan {
+    operation: Operation,
+    estimated_rows: usize,
+    actual: Option<Actual>,
+    input: Option<Box<Plan>>,
+}
+
+/// What a step of a plan does
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Operation {
+    /// Reads every entry of the DB, in order
+    Scan,
+    /// Looks up the entries satisfying `condition` in the index called `index`
+    IndexLookup { index: String, condition: String },
+    /// Keeps the entries satisfying `condition`
+    Filter { condition: String },
+    /// Sorts the entries using `keys` sort keys
+    Sort { keys: usize },
+    /// Skips this many entries
+    Offset(usize),
+    /// Keeps at most this many entries
+    Limit(usize),
+}
+
+/// What a step of a plan actually did, as measured by `Query::explain_analyze`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Actual {
+    /// How many entries the step passed on
+    pub rows: usize,
+    /// How long was spent in the step itself, not counting the steps feeding it
+    pub time: Duration,
+}
+
+impl Plan {
+    pub(crate) fn new(operation: Operation, estimated_rows: usize, input: Option<Plan>) -> Plan {
+        Plan { operation, estimated_rows, actual: None, input: input.map(Box::new) }
+    }
+
+    /// Fills in the actual figures of this step and the ones feeding it, from the innermost
+    /// step outwards
+    pub(crate) fn with_actual(mut self, actual: &[Actual]) -> Plan {
+        let (last, rest) = actual.split_last().expect("a figure for every step");
+        self.actual = Some(*last);
+        self.input = self.input.map(|input| Box::new(input.with_actual(rest)));
+        self
+    }
+
+    /// Returns what the step does
+    pub fn operation(&self) -> &Operation {
+        &self.operation
+    }
+
+    /// Returns how many entries the step is expected to pass on. Predicates can't be inspected,
+    /// so this is an upper bound which assumes they keep everything.
+    pub fn estimated_rows(&self) -> usize {
+        self.estimated_rows
+    }
+
+    /// Returns what the step actually did, if the plan came from `Query::explain_analyze`
+    pub fn actual(&self) -> Option<&Actual> {
+        self.actual.as_ref()
+    }
+
+    /// Returns the step feeding this one, or `None` for the step which finds the candidates
+    pub fn input(&self) -> Option<&Plan> {
+        self.input.as_deref()
+    }
+
+    /// Returns the steps of the plan, from the root inwards
+    pub fn steps(&self) -> PlanSteps<'_> {
+        PlanSteps { next: Some(self) }
+    }
+}
+
+/// An iterator over the steps of a plan, from the root inwards. Made by `Plan::steps`.
+#[derive(Clone, Debug)]
+pub struct PlanSteps<'p> {
+    next: Option<&'p Plan>,
+}
+
+impl<'p> Iterator for PlanSteps<'p> {
+    type Item = &'p Plan;
+
+    fn next(&mut self) -> Option<&'p Plan> {
+        let step = self.next?;
+        self.next = step.input();
+        Some(step)
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Operation::Scan => write!(f, "Scan"),
+            Operation::IndexLookup { ref index, ref condition } => {
+                write!(f, "Index Lookup using {} ({})", index, condition)
+            }
+            Operation::Filter { ref condition } => write!(f, "Filter ({})", condition),
+            Operation::Sort { keys: 1 } => write!(f, "Sort (1 key)"),
+            Operation::Sort { keys } => write!(f, "Sort ({} keys)", keys),
+            Operation::Offset(n) => write!(f, "Offset {}", n),
+            Operation::Limit(n) => write!(f, "Limit {}", n),
+        }
+    }
+}
+
+/// Prints one step per line, each indented under the step it feeds, like
+///
+/// ```text
+/// Limit 10  [estimated rows: 10]
+///   -> Filter (age > 30)  [estimated rows: 52]
+///     -> Scan  [estimated rows: 52]
+/// ```
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (depth, step) in self.steps().enumerate() {
+            if depth > 0 {
+                write!(f, "\n{:width$}-> ", "", width = 2 * depth)?;
+            }
+            write!(f, "{}  [estimated rows: {}", step.operation, step.estimated_rows)?;
+            if let Some(actual) = step.actual {
+                write!(f, ", actual rows: {}, time: {:?}", actual.rows, actual.time)?;
+            }
+            write!(f, "]")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,129 @@
+//! Splitting a DBView into keyed sub-views
+
+use std::collections::btree_map;
+use std::collections::BTreeMap;
+use std::iter::Sum;
+
+use {Aggregator, DBView};
+
+/// The entries of a DBView split into groups by key. Each group is itself a DBView over the same
+/// DB, so it lives as long as the DB does rather than as long as the view it came from.
+#[derive(Debug, PartialEq, Eq)]
+pub struct GroupedView<'a, K, T: 'a> {
+    groups: BTreeMap<K, DBView<'a, T>>,
+}
+
+impl<'a, T> DBView<'a, T> {
+    /// Splits `self` into groups of entries sharing the same `key`. Within each group, entries keep
+    /// the order they had in `self`.
+    pub fn group_by<K, F>(&self, key: F) -> GroupedView<'a, K, T>
+        where K: Ord,
+              F: Fn(&T) -> K
+    {
+        let mut groups = BTreeMap::new();
+        for &(id, entry) in &self.entries {
+            groups.entry(key(entry))
+                .or_insert_with(|| DBView { entries: Vec::new() })
+                .entries
+                .push((id, entry));
+        }
+        GroupedView { groups }
+    }
+}
+
+impl<'a, K: Ord, T> GroupedView<'a, K, T> {
+    /// Returns the group with the given key, if any entry had that key
+    pub fn get(&self, key: &K) -> Option<&DBView<'a, T>> {
+        self.groups.get(key)
+    }
+
+    /// Returns the number of groups
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Returns true if there are no groups, which happens exactly when the grouped view was empty
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Returns an iterator over the keys of the groups, in increasing order
+    pub fn keys(&self) -> btree_map::Keys<'_, K, DBView<'a, T>> {
+        self.groups.keys()
+    }
+
+    /// Returns an iterator over the groups and their keys, in increasing order of key
+    pub fn iter(&self) -> btree_map::Iter<'_, K, DBView<'a, T>> {
+        self.groups.iter()
+    }
+
+    /// Applies `f` to every group, returning the results by key
+    pub fn map_groups<R, F>(&self, f: F) -> BTreeMap<&K, R>
+        where F: Fn(&DBView<'a, T>) -> R
+    {
+        self.groups.iter().map(|(k, view)| (k, f(view))).collect()
+    }
+
+    /// Returns the number of entries in each group
+    pub fn counts(&self) -> BTreeMap<&K, usize> {
+        self.map_groups(|view| view.len())
+    }
+
+    /// Returns the sum of `f` over each group
+    pub fn sum_by<S, F>(&self, f: F) -> BTreeMap<&K, S>
+        where S: Sum<S>,
+              F: Fn(&T) -> S
+    {
+        self.map_groups(|view| view.sum_by(&f))
+    }
+
+    /// Returns the arithmetic mean of `f` over each group. Groups are never empty, so every mean
+    /// is defined.
+    pub fn mean_by<F>(&self, f: F) -> BTreeMap<&K, f64>
+        where F: Fn(&T) -> f64
+    {
+        self.map_groups(|view| view.mean_by(&f).expect("groups are never empty"))
+    }
+
+    /// Returns the entry of each group with the smallest `key_fn`
+    pub fn min_by_key<J, F>(&self, key_fn: F) -> BTreeMap<&K, &'a T>
+        where J: Ord,
+              F: Fn(&T) -> J
+    {
+        self.map_groups(|view| view.min_by_key(&key_fn).expect("groups are never empty"))
+    }
+
+    /// Returns the entry of each group with the largest `key_fn`
+    pub fn max_by_key<J, F>(&self, key_fn: F) -> BTreeMap<&K, &'a T>
+        where J: Ord,
+              F: Fn(&T) -> J
+    {
+        self.map_groups(|view| view.max_by_key(&key_fn).expect("groups are never empty"))
+    }
+
+    /// Runs a fresh aggregator, made by `make`, over each group
+    pub fn aggregate<A, F>(&self, make: F) -> BTreeMap<&K, A::Output>
+        where A: Aggregator<T>,
+              F: Fn() -> A
+    {
+        self.map_groups(|view| view.aggregate(make()))
+    }
+}
+
+impl<'a, K, T> IntoIterator for GroupedView<'a, K, T> {
+    type Item = (K, DBView<'a, T>);
+    type IntoIter = btree_map::IntoIter<K, DBView<'a, T>>;
+
+    fn into_iter(self) -> btree_map::IntoIter<K, DBView<'a, T>> {
+        self.groups.into_iter()
+    }
+}
+
+impl<'g, 'a, K, T> IntoIterator for &'g GroupedView<'a, K, T> {
+    type Item = (&'g K, &'g DBView<'a, T>);
+    type IntoIter = btree_map::Iter<'g, K, DBView<'a, T>>;
+
+    fn into_iter(self) -> btree_map::Iter<'g, K, DBView<'a, T>> {
+        self.groups.iter()
+    }
+}
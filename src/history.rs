@@ -0,0 +1,230 @@
+//! Undo and redo for the changes made to a DB
+//!
+//! Once history is enabled, every method which changes the DB records what it did as one step:
+//! the entries it inserted or removed, and the entries it handed out mutably which turned out to
+//! have changed, before and after. Edits are only seen when the DB is next used mutably, since
+//! until then the entries may still be changing, so an edit made through a DBViewMut becomes a
+//! step when the view is finished with. A committed transaction is a single step.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::mem;
+
+use {DB, RowId};
+
+pub(crate) struct History<T> {
+    clone: fn(&T) -> T,
+    eq: fn(&T, &T) -> bool,
+    size: Box<dyn Fn(&T) -> usize>,
+    memory_cap: usize,
+    memory_used: usize,
+    undo: VecDeque<Step<T>>,
+    redo: Vec<Step<T>>,
+    /// The changes made so far by the step in progress
+    current: Vec<Change<T>>,
+    /// Copies of the entries which have been handed out mutably, from before they were
+    editing: BTreeMap<RowId, T>,
+    /// How many transactions are in progress. Their changes are kept as one step.
+    groups: usize,
+}
+
+struct Step<T> {
+    changes: Vec<Change<T>>,
+    size: usize,
+}
+
+enum Change<T> {
+    Insert(RowId, T),
+    Remove(RowId, T),
+    Edit { id: RowId, before: T, after: T },
+}
+
+impl<T> History<T> {
+    pub(crate) fn inserted(&mut self, id: RowId, entry: &T) {
+        self.current.push(Change::Insert(id, (self.clone)(entry)));
+    }
+
+    pub(crate) fn removed(&mut self, id: RowId, entry: &T) {
+        self.current.push(Change::Remove(id, (self.clone)(entry)));
+    }
+
+    /// Notes that `entry` is about to be handed out mutably
+    pub(crate) fn editing(&mut self, id: RowId, entry: &T) {
+        if !self.editing.contains_key(&id) {
+            self.editing.insert(id, (self.clone)(entry));
+        }
+    }
+
+    /// Notes that `entry`, which was handed out mutably, can't be changed any more
+    pub(crate) fn edited(&mut self, id: RowId, entry: &T) {
+        if let Some(before) = self.editing.remove(&id) {
+            if !(self.eq)(&before, entry) {
+                self.current.push(Change::Edit { id, before, after: (self.clone)(entry) });
+            }
+        }
+    }
+
+    /// Finishes the step in progress, unless it is part of a transaction
+    pub(crate) fn end_step(&mut self) {
+        if self.groups > 0 || self.current.is_empty() {
+            return;
+        }
+        let changes = mem::take(&mut self.current);
+        let size = changes.iter().map(|c| self.change_size(c)).sum();
+        self.redo.clear();
+        self.push_undo(Step { changes, size });
+    }
+
+    /// Starts a transaction, whose changes will make up one step
+    pub(crate) fn begin_group(&mut self) {
+        self.groups += 1;
+    }
+
+    /// Commits a transaction
+    pub(crate) fn end_group(&mut self) {
+        self.groups -= 1;
+        self.end_step();
+    }
+
+    /// Forgets the changes made by a transaction which was rolled back
+    pub(crate) fn discard_group(&mut self) {
+        self.groups -= 1;
+        if self.groups == 0 {
+            self.current.clear();
+            self.editing.clear();
+        }
+    }
+
+    fn change_size(&self, change: &Change<T>) -> usize {
+        match *change {
+            Change::Insert(_, ref t) | Change::Remove(_, ref t) => (self.size)(t),
+            Change::Edit { ref before, ref after, .. } => (self.size)(before) + (self.size)(after),
+        }
+    }
+
+    /// Pushes `step` onto the undo stack, forgetting the oldest steps if it's over the cap
+    fn push_undo(&mut self, step: Step<T>) {
+        self.memory_used += step.size;
+        self.undo.push_back(step);
+        while self.memory_used > self.memory_cap {
+            match self.undo.pop_front() {
+                Some(oldest) => self.memory_used -= oldest.size,
+                None => break,
+            }
+        }
+    }
+}
+
+impl<T: Clone + PartialEq> DB<T> {
+    /// Starts recording changes to `self` so they can be undone, keeping as many of the most
+    /// recent steps as fit in `memory_cap` bytes. Each copy of an entry is counted as
+    /// `mem::size_of::<T>()` bytes, so memory the entries own on the heap isn't included. Use
+    /// `enable_history_with` to count that too. If history is already enabled, it is cleared.
+    pub fn enable_history(&mut self, memory_cap: usize) {
+        self.enable_history_with(memory_cap, |_: &T| mem::size_of::<T>());
+    }
+
+    /// Like `enable_history`, but each copy of an entry is counted as `size` of it bytes
+    pub fn enable_history_with<F>(&mut self, memory_cap: usize, size: F)
+        where F: Fn(&T) -> usize + 'static
+    {
+        self.sync_edits();
+        self.history = Some(History {
+            clone: T::clone,
+            eq: T::eq,
+            size: Box::new(size),
+            memory_cap,
+            memory_used: 0,
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            current: Vec::new(),
+            editing: BTreeMap::new(),
+            groups: 0,
+        });
+    }
+
+    /// Undoes the most recent step, returning false if there was nothing to undo
+    pub fn undo(&mut self) -> bool {
+        self.sync_edits();
+        let mut history = match self.history.take() {
+            Some(history) => history,
+            None => return false,
+        };
+        let step = history.undo.pop_back();
+        if let Some(ref step) = step {
+            history.memory_used -= step.size;
+            for change in step.changes.iter().rev() {
+                match *change {
+                    Change::Insert(id, _) => {
+                        self.remove(id);
+                    }
+                    Change::Remove(id, ref t) => self.restore(id, t.clone()),
+                    Change::Edit { id, ref before, .. } => self.replace(id, before.clone()),
+                }
+            }
+        }
+        let undone = step.is_some();
+        history.redo.extend(step);
+        self.history = Some(history);
+        undone
+    }
+
+    /// Redoes the most recently undone step, returning false if there was nothing to redo. Any
+    /// new change clears the steps which could be redone.
+    pub fn redo(&mut self) -> bool {
+        self.sync_edits();
+        let mut history = match self.history.take() {
+            Some(history) => history,
+            None => return false,
+        };
+        let step = history.redo.pop();
+        if let Some(ref step) = step {
+            for change in &step.changes {
+                match *change {
+                    Change::Insert(id, ref t) => self.restore(id, t.clone()),
+                    Change::Remove(id, _) => {
+                        self.remove(id);
+                    }
+                    Change::Edit { id, ref after, .. } => self.replace(id, after.clone()),
+                }
+            }
+        }
+        let redone = step.is_some();
+        if let Some(step) = step {
+            history.push_undo(step);
+        }
+        self.history = Some(history);
+        redone
+    }
+
+    fn replace(&mut self, id: RowId, entry: T) {
+        if let Some(t) = self.get_mut(id) {
+            *t = entry;
+        }
+    }
+}
+
+impl<T> DB<T> {
+    /// Stops recording changes to `self`, and forgets the ones recorded so far
+    pub fn disable_history(&mut self) {
+        self.sync_edits();
+        self.history = None;
+    }
+
+    /// Returns how many steps can be undone
+    pub fn history_len(&self) -> usize {
+        let history = match self.history {
+            Some(ref history) => history,
+            None => return 0,
+        };
+        // Edits which haven't been made into a step yet will be, if they changed anything
+        let edited = history.editing.iter().any(|(&id, before)| {
+            self.position(id).is_some_and(|i| !(history.eq)(before, &self.data[i]))
+        });
+        history.undo.len() + edited as usize
+    }
+
+    /// Returns how many steps can be redone
+    pub fn redo_len(&self) -> usize {
+        self.history.as_ref().map_or(0, |h| h.redo.len())
+    }
+}
@@ -0,0 +1,349 @@
+//! Secondary indexes over the entries of a DB
+
+use std::any::{type_name, Any};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::hash::Hash;
+use std::ops::{Bound, RangeBounds};
+
+use {DB, DBView, RowId};
+
+/// An index the DB keeps up to date as entries are inserted, removed and changed. The DB only
+/// sees indexes through this trait, since each one has its own key type.
+pub(crate) trait RowIndex<T> {
+    /// Records that the entry with the given id is now `entry`. The id must not already be
+    /// indexed.
+    fn insert(&mut self, id: RowId, entry: &T);
+
+    /// Forgets the entry with the given id, if it was indexed
+    fn remove(&mut self, id: RowId);
+
+    fn as_any(&self) -> &dyn Any;
+}
+
+type KeyFn<T, K> = dyn Fn(&T) -> K;
+
+/// Maps each key to the ids of the entries with that key
+pub(crate) struct HashIndex<T, K> {
+    key: Box<KeyFn<T, K>>,
+    ids: HashMap<K, BTreeSet<RowId>>,
+    /// The key each entry had when it was indexed, so it can be found again after the entry changes
+    keys: HashMap<RowId, K>,
+}
+
+impl<T, K> RowIndex<T> for HashIndex<T, K>
+    where T: 'static,
+          K: Hash + Eq + Clone + 'static
+{
+    fn insert(&mut self, id: RowId, entry: &T) {
+        let key = (self.key)(entry);
+        self.ids.entry(key.clone()).or_default().insert(id);
+        self.keys.insert(id, key);
+    }
+
+    fn remove(&mut self, id: RowId) {
+        if let Some(key) = self.keys.remove(&id) {
+            if let Entry::Occupied(mut ids) = self.ids.entry(key) {
+                ids.get_mut().remove(&id);
+                if ids.get().is_empty() {
+                    ids.remove();
+                }
+            }
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl<T, K: Hash + Eq> HashIndex<T, K> {
+    /// Returns the current key of `entry`
+    pub(crate) fn key_of(&self, entry: &T) -> K {
+        (self.key)(entry)
+    }
+
+    /// Returns how many entries were indexed under `key`
+    pub(crate) fn count(&self, key: &K) -> usize {
+        self.ids.get(key).map_or(0, |ids| ids.len())
+    }
+}
+
+/// Keeps the ids of entries sorted by key, so ranges of keys can be found without a scan
+pub(crate) struct OrderedIndex<T, K> {
+    key: Box<KeyFn<T, K>>,
+    ids: BTreeMap<K, BTreeSet<RowId>>,
+    /// The key each entry had when it was indexed, so it can be found again after the entry changes
+    keys: HashMap<RowId, K>,
+}
+
+impl<T, K> RowIndex<T> for OrderedIndex<T, K>
+    where T: 'static,
+          K: Ord + Clone + 'static
+{
+    fn insert(&mut self, id: RowId, entry: &T) {
+        let key = (self.key)(entry);
+        self.ids.entry(key.clone()).or_default().insert(id);
+        self.keys.insert(id, key);
+    }
+
+    fn remove(&mut self, id: RowId) {
+        if let Some(key) = self.keys.remove(&id) {
+            let now_empty = match self.ids.get_mut(&key) {
+                Some(ids) => {
+                    ids.remove(&id);
+                    ids.is_empty()
+                }
+                None => false,
+            };
+            if now_empty {
+                self.ids.remove(&key);
+            }
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl<T, K: Ord> OrderedIndex<T, K> {
+    /// Returns the current key of `entry`
+    pub(crate) fn key_of(&self, entry: &T) -> K {
+        (self.key)(entry)
+    }
+
+    /// Returns how many entries were indexed under a key in `range`
+    pub(crate) fn count_range<R: RangeBounds<K>>(&self, range: &R) -> usize {
+        if is_backwards(range) {
+            return 0;
+        }
+        self.ids.range((range.start_bound(), range.end_bound())).map(|(_, ids)| ids.len()).sum()
+    }
+}
+
+impl<T: 'static> DB<T> {
+    /// Creates a hash index called `name` over the `key` of each entry, replacing any existing
+    /// index with that name. The index is kept up to date as entries are inserted, removed, or
+    /// changed through a DBViewMut.
+    pub fn create_index<K, F>(&mut self, name: &str, key: F)
+        where K: Hash + Eq + Clone + 'static,
+              F: Fn(&T) -> K + 'static
+    {
+        let mut index = HashIndex { key: Box::new(key), ids: HashMap::new(), keys: HashMap::new() };
+        self.fill_index(name, &mut index);
+        self.indexes.insert(name.to_string(), Box::new(index));
+    }
+
+    /// Creates an ordered index called `name` over the `key` of each entry, replacing any existing
+    /// index with that name. Like a hash index it is kept up to date as entries change, and it
+    /// can also answer range queries and list entries in key order.
+    pub fn create_ordered_index<K, F>(&mut self, name: &str, key: F)
+        where K: Ord + Clone + 'static,
+              F: Fn(&T) -> K + 'static
+    {
+        let mut index =
+            OrderedIndex { key: Box::new(key), ids: BTreeMap::new(), keys: HashMap::new() };
+        self.fill_index(name, &mut index);
+        self.indexes.insert(name.to_string(), Box::new(index));
+    }
+
+    /// Removes the index called `name`, returning whether there was one
+    pub fn drop_index(&mut self, name: &str) -> bool {
+        self.sync_edits();
+        self.indexes.remove(name).is_some()
+    }
+
+    /// Returns true if `self` has an index called `name`
+    pub fn has_index(&self, name: &str) -> bool {
+        self.indexes.contains_key(name)
+    }
+
+    /// Brings the indexes up to date with entries changed through `get_mut`, `iter_mut` or a
+    /// DBViewMut. Until this is done, lookups can't trust the index keys of those entries, so
+    /// each lookup checks every one of them directly: after editing the whole DB in place, a
+    /// lookup costs a full scan. Any method taking `&mut self` does this as well.
+    pub fn refresh_indexes(&mut self) {
+        self.sync_edits();
+    }
+
+    /// Creates a new DBView containing all entries in `self` whose key in the hash index `index`
+    /// equals `key`, in the order they appear in `self`. This takes time proportional to the
+    /// number of matches, plus the number of entries changed in place since the indexes were last
+    /// refreshed; see `refresh_indexes`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no hash index called `index` with keys of type `K`.
+    pub fn select_eq<K>(&self, index: &str, key: &K) -> DBView<'_, T>
+        where K: Hash + Eq + Clone + 'static
+    {
+        let index: &HashIndex<T, K> = self.typed_index(index);
+        let indexed = index.ids.get(key).into_iter().flat_map(|ids| ids.iter().cloned());
+        self.view_of_indexed(indexed, |entry| (index.key)(entry) == *key)
+    }
+
+    /// Creates a new DBView containing all entries in `self` whose key in the ordered index `index`
+    /// lies in `range`, sorted by key. Entries with equal keys are in the order they appear in
+    /// `self`. Like `select_eq`, this also checks each entry changed in place since the indexes
+    /// were last refreshed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no ordered index called `index` with keys of type `K`.
+    pub fn select_range<K, R>(&self, index: &str, range: R) -> DBView<'_, T>
+        where K: Ord + Clone + 'static,
+              R: RangeBounds<K>
+    {
+        let index: &OrderedIndex<T, K> = self.typed_index(index);
+        // `BTreeMap::range` panics on backwards ranges, but they are simply empty here
+        let bounds = if is_backwards(&range) {
+            None
+        } else {
+            Some((range.start_bound().cloned(), range.end_bound().cloned()))
+        };
+        let indexed = bounds.map(|bounds| index.ids.range(bounds))
+            .into_iter()
+            .flatten()
+            .flat_map(|(k, ids)| ids.iter().map(move |&id| (k, id)));
+        self.view_in_key_order(index, indexed, |k| range.contains(k), None, false)
+    }
+
+    /// Creates a new DBView containing every entry of `self`, sorted by its key in the ordered
+    /// index `index`
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no ordered index called `index` with keys of type `K`.
+    pub fn select_ordered<K>(&self, index: &str) -> DBView<'_, T>
+        where K: Ord + Clone + 'static
+    {
+        self.select_range::<K, _>(index, ..)
+    }
+
+    /// Creates a new DBView containing the `n` entries of `self` with the smallest keys in the
+    /// ordered index `index`, sorted by key
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no ordered index called `index` with keys of type `K`.
+    pub fn select_first<K>(&self, index: &str, n: usize) -> DBView<'_, T>
+        where K: Ord + Clone + 'static
+    {
+        let index: &OrderedIndex<T, K> = self.typed_index(index);
+        let indexed = index.ids.iter().flat_map(|(k, ids)| ids.iter().map(move |&id| (k, id)));
+        self.view_in_key_order(index, indexed, |_| true, Some(n), false)
+    }
+
+    /// Creates a new DBView containing the `n` entries of `self` with the largest keys in the
+    /// ordered index `index`, sorted by key
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no ordered index called `index` with keys of type `K`.
+    pub fn select_last<K>(&self, index: &str, n: usize) -> DBView<'_, T>
+        where K: Ord + Clone + 'static
+    {
+        let index: &OrderedIndex<T, K> = self.typed_index(index);
+        let indexed =
+            index.ids.iter().rev().flat_map(|(k, ids)| ids.iter().rev().map(move |&id| (k, id)));
+        self.view_in_key_order(index, indexed, |_| true, Some(n), true)
+    }
+
+    /// Makes a view of the `(key, id)` pairs an ordered index returned for a lookup, which must be
+    /// sorted by key and then id (or the reverse of that, if `reverse` is set). As with
+    /// `view_of_indexed`, entries changed in place since the index was last updated are checked
+    /// against `in_range` using their current key. At most `limit` entries are kept, and the
+    /// result is always in increasing order.
+    fn view_in_key_order<'s, K, I, F>(&'s self,
+                                      index: &OrderedIndex<T, K>,
+                                      indexed: I,
+                                      in_range: F,
+                                      limit: Option<usize>,
+                                      reverse: bool)
+                                      -> DBView<'s, T>
+        where K: Ord + Clone + 'static,
+              I: Iterator<Item = (&'s K, RowId)>,
+              F: Fn(&K) -> bool
+    {
+        let limit = limit.unwrap_or(usize::MAX);
+        let mut found: Vec<(&K, RowId)> =
+            indexed.filter(|&(_, id)| !self.dirty.contains(&id)).take(limit).collect();
+        let changed: Vec<(K, RowId)> = self.dirty
+            .iter()
+            .filter_map(|&id| self.position(id).map(|i| ((index.key)(&self.data[i]), id)))
+            .filter(|(k, _)| in_range(k))
+            .collect();
+        if !changed.is_empty() {
+            found.extend(changed.iter().map(|(k, id)| (k, *id)));
+            if reverse {
+                found.sort_by(|a, b| b.cmp(a));
+            } else {
+                found.sort();
+            }
+            found.truncate(limit);
+        }
+        if reverse {
+            found.reverse();
+        }
+        let entries = found.into_iter().map(|(_, id)| self.indexed_entry(id)).collect();
+        DBView { entries }
+    }
+
+    fn indexed_entry(&self, id: RowId) -> (RowId, &T) {
+        (id, &self.data[self.position(id).expect("indexed entries are in the DB")])
+    }
+
+    /// Brings a freshly made index up to date with every entry of `self`
+    pub(crate) fn fill_index<I: RowIndex<T>>(&mut self, name: &str, index: &mut I) {
+        self.sync_edits();
+        self.indexes.remove(name);
+        for (&id, entry) in self.ids.iter().zip(self.data.iter()) {
+            index.insert(id, entry);
+        }
+    }
+
+    /// Looks up the index called `name`, which must be an `I`
+    pub(crate) fn typed_index<I: 'static>(&self, name: &str) -> &I {
+        let index = self.indexes.get(name).unwrap_or_else(|| panic!("no index called `{}`", name));
+        index.as_any().downcast_ref().unwrap_or_else(|| {
+            panic!("index `{}` is not a `{}`; check the kind of index and its key type",
+                   name,
+                   type_name::<I>())
+        })
+    }
+
+    /// Makes a view, in the order of `self`, of the ids an index returned for a lookup. `indexed`
+    /// must be in increasing order. Entries changed in place since the index was last updated may
+    /// have stale keys in it, so those are dropped from `indexed` and checked against `matches`
+    /// directly instead.
+    pub(crate) fn view_of_indexed<I, F>(&self, indexed: I, matches: F) -> DBView<'_, T>
+        where I: Iterator<Item = RowId>,
+              F: Fn(&T) -> bool
+    {
+        let mut entries: Vec<_> = indexed.filter(|id| !self.dirty.contains(id))
+            .map(|id| self.indexed_entry(id))
+            .collect();
+        if !self.dirty.is_empty() {
+            let before = entries.len();
+            entries.extend(self.dirty
+                .iter()
+                .filter_map(|&id| self.position(id).map(|i| (id, &self.data[i])))
+                .filter(|&(_, entry)| matches(entry)));
+            if entries.len() != before {
+                entries.sort_by_key(|&(id, _)| id);
+            }
+        }
+        DBView { entries }
+    }
+}
+
+fn is_backwards<K: Ord, R: RangeBounds<K>>(range: &R) -> bool {
+    match (range.start_bound(), range.end_bound()) {
+        (Bound::Included(start), Bound::Included(end)) => start > end,
+        (Bound::Included(start), Bound::Excluded(end)) |
+        (Bound::Excluded(start), Bound::Included(end)) |
+        (Bound::Excluded(start), Bound::Excluded(end)) => start >= end,
+        _ => false,
+    }
+}
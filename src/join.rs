@@ -0,0 +1,246 @@
+//! Relational joins between two DBViews
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::slice;
+use std::vec;
+
+use DBView;
+
+/// The matching pairs of entries produced by joining two DBViews. The two sides may come from
+/// different DBs, and each side keeps the lifetime of its own DB.
+#[derive(Debug, PartialEq, Eq)]
+pub struct JoinView<'a, 'b, A: 'a, B: 'b> {
+    pairs: Vec<(&'a A, &'b B)>,
+}
+
+/// The pairs produced by a left outer join of two DBViews. Every entry of the left view appears at
+/// least once, paired with `None` if nothing on the right matched it.
+#[derive(Debug, PartialEq, Eq)]
+pub struct LeftJoinView<'a, 'b, A: 'a, B: 'b> {
+    pairs: Vec<(&'a A, Option<&'b B>)>,
+}
+
+/// Joins `view_a` with `view_b`, pairing every entry of `view_a` with every entry of `view_b` that
+/// has an equal key. This is a hash join, the same as `hash_join`.
+pub fn join<'a, 'b, A, B, K, FA, FB>(view_a: &DBView<'a, A>,
+                                      view_b: &DBView<'b, B>,
+                                      key_a: FA,
+                                      key_b: FB)
+                                      -> JoinView<'a, 'b, A, B>
+    where K: Hash + Eq,
+          FA: Fn(&A) -> K,
+          FB: Fn(&B) -> K
+{
+    hash_join(view_a, view_b, key_a, key_b)
+}
+
+/// Joins two views by building a hash table over the keys of `view_b` and probing it with each
+/// entry of `view_a`. Pairs come out in the order of `view_a`, then of `view_b`.
+pub fn hash_join<'a, 'b, A, B, K, FA, FB>(view_a: &DBView<'a, A>,
+                                           view_b: &DBView<'b, B>,
+                                           key_a: FA,
+                                           key_b: FB)
+                                           -> JoinView<'a, 'b, A, B>
+    where K: Hash + Eq,
+          FA: Fn(&A) -> K,
+          FB: Fn(&B) -> K
+{
+    let table = build_table(view_b, key_b);
+    let mut pairs = Vec::new();
+    for a in view_a.iter() {
+        if let Some(bs) = table.get(&key_a(a)) {
+            pairs.extend(bs.iter().map(|&b| (a, b)));
+        }
+    }
+    JoinView { pairs }
+}
+
+/// Joins two views by comparing every entry of `view_a` against every entry of `view_b`. This only
+/// needs `PartialEq` keys, at the cost of quadratic time. Pairs come out in the same order as
+/// `hash_join`.
+pub fn nested_loop_join<'a, 'b, A, B, K, FA, FB>(view_a: &DBView<'a, A>,
+                                                  view_b: &DBView<'b, B>,
+                                                  key_a: FA,
+                                                  key_b: FB)
+                                                  -> JoinView<'a, 'b, A, B>
+    where K: PartialEq,
+          FA: Fn(&A) -> K,
+          FB: Fn(&B) -> K
+{
+    let keys_b: Vec<(K, &'b B)> = view_b.iter().map(|b| (key_b(b), b)).collect();
+    let mut pairs = Vec::new();
+    for a in view_a.iter() {
+        let k = key_a(a);
+        pairs.extend(keys_b.iter().filter(|(kb, _)| *kb == k).map(|&(_, b)| (a, b)));
+    }
+    JoinView { pairs }
+}
+
+/// Left outer join of two views using a hash table over the keys of `view_b`
+pub fn left_join<'a, 'b, A, B, K, FA, FB>(view_a: &DBView<'a, A>,
+                                           view_b: &DBView<'b, B>,
+                                           key_a: FA,
+                                           key_b: FB)
+                                           -> LeftJoinView<'a, 'b, A, B>
+    where K: Hash + Eq,
+          FA: Fn(&A) -> K,
+          FB: Fn(&B) -> K
+{
+    let table = build_table(view_b, key_b);
+    let mut pairs = Vec::new();
+    for a in view_a.iter() {
+        match table.get(&key_a(a)) {
+            Some(bs) => pairs.extend(bs.iter().map(|&b| (a, Some(b)))),
+            None => pairs.push((a, None)),
+        }
+    }
+    LeftJoinView { pairs }
+}
+
+/// Left outer join of two views by comparing every pair of entries, needing only `PartialEq` keys
+pub fn nested_loop_left_join<'a, 'b, A, B, K, FA, FB>(view_a: &DBView<'a, A>,
+                                                       view_b: &DBView<'b, B>,
+                                                       key_a: FA,
+                                                       key_b: FB)
+                                                       -> LeftJoinView<'a, 'b, A, B>
+    where K: PartialEq,
+          FA: Fn(&A) -> K,
+          FB: Fn(&B) -> K
+{
+    let keys_b: Vec<(K, &'b B)> = view_b.iter().map(|b| (key_b(b), b)).collect();
+    let mut pairs = Vec::new();
+    for a in view_a.iter() {
+        let k = key_a(a);
+        let before = pairs.len();
+        pairs.extend(keys_b.iter().filter(|(kb, _)| *kb == k).map(|&(_, b)| (a, Some(b))));
+        if pairs.len() == before {
+            pairs.push((a, None));
+        }
+    }
+    LeftJoinView { pairs }
+}
+
+/// Pairs every entry of `view_a` with every entry of `view_b`
+pub fn cross_join<'a, 'b, A, B>(view_a: &DBView<'a, A>,
+                                view_b: &DBView<'b, B>)
+                                -> JoinView<'a, 'b, A, B> {
+    let mut pairs = Vec::with_capacity(view_a.len() * view_b.len());
+    for a in view_a.iter() {
+        pairs.extend(view_b.iter().map(|b| (a, b)));
+    }
+    JoinView { pairs }
+}
+
+fn build_table<'b, B, K, F>(view: &DBView<'b, B>, key: F) -> HashMap<K, Vec<&'b B>>
+    where K: Hash + Eq,
+          F: Fn(&B) -> K
+{
+    let mut table: HashMap<K, Vec<&'b B>> = HashMap::new();
+    for b in view.iter() {
+        table.entry(key(b)).or_default().push(b);
+    }
+    table
+}
+
+impl<'a, 'b, A, B> JoinView<'a, 'b, A, B> {
+    /// Returns the number of pairs in the join
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    /// Returns true if nothing matched
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    /// Returns an iterator over the pairs of the join
+    pub fn iter<'v>(&'v self) -> JoinIter<'v, 'a, 'b, A, B> {
+        JoinIter { inner: self.pairs.iter() }
+    }
+}
+
+impl<'a, 'b, A, B> LeftJoinView<'a, 'b, A, B> {
+    /// Returns the number of pairs in the join
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    /// Returns true if the left view was empty
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    /// Returns an iterator over the pairs of the join
+    pub fn iter<'v>(&'v self) -> LeftJoinIter<'v, 'a, 'b, A, B> {
+        LeftJoinIter { inner: self.pairs.iter() }
+    }
+}
+
+/// A borrowing iterator over the pairs of a JoinView
+#[derive(Debug)]
+pub struct JoinIter<'v, 'a: 'v, 'b: 'v, A: 'a, B: 'b> {
+    inner: slice::Iter<'v, (&'a A, &'b B)>,
+}
+
+forward_iterator!(JoinIter<'v, 'a, 'b; A, B>, (&'a A, &'b B), |&pair| pair);
+
+/// An owning iterator over the pairs of a JoinView
+#[derive(Debug)]
+pub struct JoinIntoIter<'a, 'b, A: 'a, B: 'b> {
+    inner: vec::IntoIter<(&'a A, &'b B)>,
+}
+
+forward_iterator!(JoinIntoIter<'a, 'b; A, B>, (&'a A, &'b B), |pair| pair);
+
+/// A borrowing iterator over the pairs of a LeftJoinView
+#[derive(Debug)]
+pub struct LeftJoinIter<'v, 'a: 'v, 'b: 'v, A: 'a, B: 'b> {
+    inner: slice::Iter<'v, (&'a A, Option<&'b B>)>,
+}
+
+forward_iterator!(LeftJoinIter<'v, 'a, 'b; A, B>, (&'a A, Option<&'b B>), |&pair| pair);
+
+/// An owning iterator over the pairs of a LeftJoinView
+#[derive(Debug)]
+pub struct LeftJoinIntoIter<'a, 'b, A: 'a, B: 'b> {
+    inner: vec::IntoIter<(&'a A, Option<&'b B>)>,
+}
+
+forward_iterator!(LeftJoinIntoIter<'a, 'b; A, B>, (&'a A, Option<&'b B>), |pair| pair);
+
+impl<'a, 'b, A, B> IntoIterator for JoinView<'a, 'b, A, B> {
+    type Item = (&'a A, &'b B);
+    type IntoIter = JoinIntoIter<'a, 'b, A, B>;
+
+    fn into_iter(self) -> JoinIntoIter<'a, 'b, A, B> {
+        JoinIntoIter { inner: self.pairs.into_iter() }
+    }
+}
+
+impl<'v, 'a, 'b, A, B> IntoIterator for &'v JoinView<'a, 'b, A, B> {
+    type Item = (&'a A, &'b B);
+    type IntoIter = JoinIter<'v, 'a, 'b, A, B>;
+
+    fn into_iter(self) -> JoinIter<'v, 'a, 'b, A, B> {
+        self.iter()
+    }
+}
+
+impl<'a, 'b, A, B> IntoIterator for LeftJoinView<'a, 'b, A, B> {
+    type Item = (&'a A, Option<&'b B>);
+    type IntoIter = LeftJoinIntoIter<'a, 'b, A, B>;
+
+    fn into_iter(self) -> LeftJoinIntoIter<'a, 'b, A, B> {
+        LeftJoinIntoIter { inner: self.pairs.into_iter() }
+    }
+}
+
+impl<'v, 'a, 'b, A, B> IntoIterator for &'v LeftJoinView<'a, 'b, A, B> {
+    type Item = (&'a A, Option<&'b B>);
+    type IntoIter = LeftJoinIter<'v, 'a, 'b, A, B>;
+
+    fn into_iter(self) -> LeftJoinIter<'v, 'a, 'b, A, B> {
+        self.iter()
+    }
+}
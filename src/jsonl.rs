@@ -0,0 +1,319 @@
+//! Reading and writing records as JSON Lines: one JSON object per line, behind the `jsonl` feature
+//!
+//! Each record is an object with a member for each field. Lists are arrays, and bytes are hex
+//! strings. Integers are accepted by float fields too. Floats which JSON can't hold (infinities
+//! and NaN) are written as `null`. Members with no matching field are skipped, so other data,
+//! including nested objects, can sit alongside the record. Loading reads one line at a time, so
+//! the text of the whole input is never held at once. Blank lines are skipped.
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+use serde::de::{Deserialize, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde_json::{self, Value as Json};
+
+use csv::from_hex;
+use {DB, DBView, FieldType, Record, TypeError, Value};
+
+/// What loading does with a line which isn't a valid record
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorMode {
+    /// Stop loading, and return the error
+    Strict,
+    /// Carry on with the next line, keeping the error to return at the end
+    Skip,
+}
+
+/// Something that went wrong loading JSON Lines, and the line it went wrong on
+#[derive(Debug)]
+pub struct JsonlError {
+    line: usize,
+    kind: JsonlErrorKind,
+}
+
+#[derive(Debug)]
+pub enum JsonlErrorKind {
+    Io(io::Error),
+    /// The line isn't valid JSON
+    Syntax { column: usize, message: String },
+    /// The line is valid JSON, but not an object
+    NotAnObject,
+    /// The field called `field` was given an object, which no field can hold
+    ObjectValue { field: String },
+    /// The object has two members with this name
+    DuplicateField(String),
+    /// The field called `field` holds bytes, but was given something other than a hex string
+    BadBytes { field: String },
+    /// The values of the object don't make a record
+    Type(TypeError),
+}
+
+impl JsonlError {
+    fn new(line: usize, kind: JsonlErrorKind) -> JsonlError {
+        JsonlError { line, kind }
+    }
+
+    /// Returns the line of the input the error is on, counting from 1
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Returns what went wrong
+    pub fn kind(&self) -> &JsonlErrorKind {
+        &self.kind
+    }
+
+    /// Returns what went wrong, consuming the error
+    pub fn into_kind(self) -> JsonlErrorKind {
+        self.kind
+    }
+}
+
+impl fmt::Display for JsonlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            JsonlErrorKind::Io(ref e) => write!(f, "{}", e)?,
+            JsonlErrorKind::Syntax { column, ref message } => {
+                return write!(f, "{} at line {}, column {}", message, self.line, column);
+            }
+            JsonlErrorKind::NotAnObject => write!(f, "expected an object")?,
+            JsonlErrorKind::ObjectValue { ref field } => {
+                write!(f, "field `{}` was given an object", field)?
+            }
+            JsonlErrorKind::DuplicateField(ref name) => write!(f, "duplicate field `{}`", name)?,
+            JsonlErrorKind::BadBytes { ref field } => {
+                write!(f, "field `{}` holds bytes, which must be a hex string", field)?
+            }
+            JsonlErrorKind::Type(ref e) => write!(f, "{}", e)?,
+        }
+        write!(f, " at line {}", self.line)
+    }
+}
+
+impl Error for JsonlError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self.kind {
+            JsonlErrorKind::Io(ref e) => Some(e),
+            JsonlErrorKind::Type(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// The members of an object, in order, or nothing if a line holds some other JSON value.
+/// Duplicate members are kept, unlike in `serde_json::Map`, so they can be reported.
+enum Line {
+    Object(Vec<(String, Json)>),
+    Other,
+}
+
+impl<'de> Deserialize<'de> for Line {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Line, D::Error> {
+        deserializer.deserialize_any(LineVisitor)
+    }
+}
+
+struct LineVisitor;
+
+impl<'de> Visitor<'de> for LineVisitor {
+    type Value = Line;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a JSON value")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Line, A::Error> {
+        let mut members = Vec::new();
+        while let Some(member) = map.next_entry()? {
+            members.push(member);
+        }
+        Ok(Line::Object(members))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Line, A::Error> {
+        while seq.next_element::<IgnoredAny>()?.is_some() {}
+        Ok(Line::Other)
+    }
+
+    fn visit_bool<E>(self, _: bool) -> Result<Line, E> {
+        Ok(Line::Other)
+    }
+
+    fn visit_i64<E>(self, _: i64) -> Result<Line, E> {
+        Ok(Line::Other)
+    }
+
+    fn visit_u64<E>(self, _: u64) -> Result<Line, E> {
+        Ok(Line::Other)
+    }
+
+    fn visit_f64<E>(self, _: f64) -> Result<Line, E> {
+        Ok(Line::Other)
+    }
+
+    fn visit_str<E>(self, _: &str) -> Result<Line, E> {
+        Ok(Line::Other)
+    }
+
+    fn visit_unit<E>(self) -> Result<Line, E> {
+        Ok(Line::Other)
+    }
+}
+
+/// Turns a parse error into a syntax error. The line number `serde_json` gives is always 1, as
+/// it only sees one line, so it's left out of the message.
+fn syntax_error(e: serde_json::Error) -> JsonlErrorKind {
+    let message = e.to_string();
+    let message = match message.rfind(" at line ") {
+        Some(end) => message[..end].to_string(),
+        None => message,
+    };
+    JsonlErrorKind::Syntax { column: e.column(), message }
+}
+
+/// Converts the JSON value of the field called `field`
+fn to_value(field: &str, json: Json) -> Result<Value, JsonlErrorKind> {
+    let value = match json {
+        Json::Null => Value::Null,
+        Json::Bool(b) => Value::Bool(b),
+        Json::Number(n) => match n.as_i64() {
+            Some(i) => Value::Int(i),
+            None => Value::Float(n.as_f64().unwrap_or(f64::NAN)),
+        },
+        Json::String(s) => Value::Str(s),
+        Json::Array(values) => {
+            let values = values.into_iter().map(|json| to_value(field, json));
+            Value::List(values.collect::<Result<_, _>>()?)
+        }
+        Json::Object(_) => return Err(JsonlErrorKind::ObjectValue { field: field.to_string() }),
+    };
+    Ok(value)
+}
+
+/// Makes a record of type `T` from one line of JSON
+fn parse_record<T: Record>(text: &str) -> Result<T, JsonlErrorKind> {
+    let members = match serde_json::from_str(text).map_err(syntax_error)? {
+        Line::Object(members) => members,
+        Line::Other => return Err(JsonlErrorKind::NotAnObject),
+    };
+    let schema = T::schema();
+    let mut values: Vec<Option<Value>> = vec![None; schema.len()];
+    for (name, json) in members {
+        // Members with no matching field are skipped, whatever they hold
+        let i = match schema.position(&name) {
+            Some(i) => i,
+            None => continue,
+        };
+        if values[i].is_some() {
+            return Err(JsonlErrorKind::DuplicateField(name));
+        }
+        let field = &schema.fields()[i];
+        let value = match (field.ty, to_value(&name, json)?) {
+            (FieldType::Float, Value::Int(i)) => Value::Float(i as f64),
+            (FieldType::Bytes, Value::Str(s)) => match from_hex(&s) {
+                Some(bytes) => Value::Bytes(bytes),
+                None => return Err(JsonlErrorKind::BadBytes { field: name }),
+            },
+            (FieldType::Bytes, Value::Null) => Value::Null,
+            (FieldType::Bytes, _) => return Err(JsonlErrorKind::BadBytes { field: name }),
+            (_, value) => value,
+        };
+        values[i] = Some(value);
+    }
+    // Fields left out are `NULL`, which is only allowed if they're nullable
+    let values = values.into_iter().map(|v| v.unwrap_or(Value::Null)).collect();
+    T::from_values(values).map_err(JsonlErrorKind::Type)
+}
+
+fn write_string<W: Write>(output: &mut W, s: &str) -> io::Result<()> {
+    serde_json::to_writer(output, s).map_err(io::Error::from)
+}
+
+fn write_value<W: Write>(output: &mut W, value: &Value) -> io::Result<()> {
+    match *value {
+        Value::Null => write!(output, "null"),
+        Value::Bool(b) => write!(output, "{}", b),
+        Value::Int(i) => write!(output, "{}", i),
+        Value::Float(x) if x.is_finite() => write!(output, "{:?}", x),
+        Value::Float(_) => write!(output, "null"),
+        Value::Str(ref s) => write_string(output, s),
+        Value::Bytes(ref bytes) => {
+            write!(output, "\"")?;
+            for byte in bytes {
+                write!(output, "{:02x}", byte)?;
+            }
+            write!(output, "\"")
+        }
+        Value::List(ref values) => {
+            write!(output, "[")?;
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    write!(output, ",")?;
+                }
+                write_value(output, value)?;
+            }
+            write!(output, "]")
+        }
+    }
+}
+
+impl<T: Record> DB<T> {
+    /// Loads records from JSON Lines, stopping at the first line which isn't a valid record
+    pub fn load_jsonl<R: Read>(input: R) -> Result<DB<T>, JsonlError> {
+        DB::load_jsonl_with(input, ErrorMode::Strict).map(|(db, _)| db)
+    }
+
+    /// Loads records from JSON Lines, dealing with lines which aren't valid records as `mode`
+    /// says. Returns the DB along with the errors for any lines which were skipped. Errors
+    /// reading the input always stop loading.
+    pub fn load_jsonl_with<R: Read>(input: R,
+                                    mode: ErrorMode)
+                                    -> Result<(DB<T>, Vec<JsonlError>), JsonlError> {
+        let mut input = BufReader::new(input);
+        let mut db = DB::new(Vec::new());
+        let mut skipped = Vec::new();
+        let mut text = String::new();
+        let mut line = 0;
+        loop {
+            text.clear();
+            line += 1;
+            match input.read_line(&mut text) {
+                Ok(0) => return Ok((db, skipped)),
+                Ok(_) => {}
+                Err(e) => return Err(JsonlError::new(line, JsonlErrorKind::Io(e))),
+            }
+            if text.trim().is_empty() {
+                continue;
+            }
+            match parse_record(&text) {
+                Ok(record) => {
+                    db.insert(record);
+                }
+                Err(kind) if mode == ErrorMode::Skip => skipped.push(JsonlError::new(line, kind)),
+                Err(kind) => return Err(JsonlError::new(line, kind)),
+            }
+        }
+    }
+}
+
+impl<'a, T: Record> DBView<'a, T> {
+    /// Writes the records of `self` as JSON Lines, in order
+    pub fn write_jsonl<W: Write>(&self, output: W) -> io::Result<()> {
+        let schema = T::schema();
+        let mut output = io::BufWriter::new(output);
+        for entry in self {
+            write!(output, "{{")?;
+            for (i, (name, value)) in schema.names().zip(entry.values()).enumerate() {
+                if i > 0 {
+                    write!(output, ",")?;
+                }
+                write_string(&mut output, name)?;
+                write!(output, ":")?;
+                write_value(&mut output, &value)?;
+            }
+            writeln!(output, "}}")?;
+        }
+        output.flush()
+    }
+}
@@ -1,130 +1,798 @@
-#[derive(Debug, PartialEq, Eq)]
+#[cfg(any(feature = "serde", feature = "jsonl"))]
+extern crate serde;
+#[cfg(feature = "jsonl")]
+extern crate serde_json;
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::mem;
+use std::slice;
+use std::thread;
+use std::vec;
+
+// Every iterator in this crate is a thin wrapper around a std iterator, so they all forward the same
+// set of methods. This macro writes that forwarding once.
+macro_rules! forward_iterator {
+    ($name:ident < $($lt:lifetime),* ; $($t:ident),+ >,
+     $item:ty,
+     |$x:pat| $map:expr) => {
+        impl<$($lt,)* $($t),+> Iterator for $name<$($lt,)* $($t),+> {
+            type Item = $item;
+
+            fn next(&mut self) -> Option<$item> {
+                self.inner.next().map(|$x| $map)
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                self.inner.size_hint()
+            }
+        }
+
+        impl<$($lt,)* $($t),+> DoubleEndedIterator for $name<$($lt,)* $($t),+> {
+            fn next_back(&mut self) -> Option<$item> {
+                self.inner.next_back().map(|$x| $map)
+            }
+        }
+
+        impl<$($lt,)* $($t),+> ExactSizeIterator for $name<$($lt,)* $($t),+> {
+            fn len(&self) -> usize {
+                self.inner.len()
+            }
+        }
+
+        impl<$($lt,)* $($t),+> ::std::iter::FusedIterator for $name<$($lt,)* $($t),+> {}
+    }
+}
+
+mod aggregate;
+mod binary;
+mod csv;
+mod explain;
+mod group;
+mod history;
+mod index;
+mod join;
+#[cfg(feature = "jsonl")]
+mod jsonl;
+pub mod predicate;
+mod query;
+mod record;
+#[cfg(feature = "serde")]
+mod serialize;
+mod set;
+pub mod sql;
+mod table;
+mod transaction;
+mod update;
+mod value;
+mod wal;
+
+pub use aggregate::Aggregator;
+pub use binary::BinaryError;
+pub use csv::{CsvError, CsvErrorKind, CsvFormat};
+pub use explain::{Actual, Operation, Plan, PlanSteps};
+pub use group::GroupedView;
+#[cfg(feature = "jsonl")]
+pub use jsonl::{ErrorMode, JsonlError, JsonlErrorKind};
+pub use join::{JoinView, LeftJoinView, JoinIter, JoinIntoIter, LeftJoinIter, LeftJoinIntoIter};
+pub use predicate::Predicate;
+pub use query::Query;
+pub use record::{Field, FieldAccess, FieldType, FieldValue, Record, Schema};
+pub use table::{Row, RowMut, RowMutIter, Table, TableViewMut, TypeError};
+pub use transaction::Transaction;
+pub use update::{Change, UpdateReport};
+pub use value::Value;
+pub use wal::WalError;
+pub use join::{join, hash_join, nested_loop_join, left_join, nested_loop_left_join, cross_join};
+
 pub struct DB<T> {
     data: Vec<T>,
+    /// The id of each entry in `data`, kept in increasing order so they can be binary searched
+    ids: Vec<RowId>,
+    next_id: u64,
+    indexes: BTreeMap<String, Box<dyn index::RowIndex<T>>>,
+    /// Entries which have been handed out mutably since the indexes were last brought up to date.
+    /// Their index keys may be stale, so lookups check them directly.
+    dirty: BTreeSet<RowId>,
+    history: Option<history::History<T>>,
+    wal: Option<wal::Wal<T>>,
 }
 
+/// Identifies a single entry of a DB. Ids are never reused, so an id stays valid (and keeps
+/// referring to the same entry) until that entry is removed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RowId(u64);
+
 /// An immutably borrowed subset of a DB
-///
-/// NB: (nota bene, or "take special note"): You will need to be explcit about the liftimes in this
-/// struct
-#[derive(Debug, PartialEq, Eq)]
-pub struct DBView<T> {
-    entries: Vec<&T>,
+#[derive(Debug)]
+pub struct DBView<'a, T: 'a> {
+    entries: Vec<(RowId, &'a T)>,
 }
 
 /// An mutably borrowed subset of a DB
-///
-/// NB: You will need to be explcit about the liftimes in this struct
-#[derive(Debug, PartialEq, Eq)]
-pub struct DBViewMut<T> {
-    entries: Vec<&mut T>,
+#[derive(Debug)]
+pub struct DBViewMut<'a, T: 'a> {
+    entries: Vec<(RowId, &'a mut T)>,
+}
+
+type Comparator<T> = dyn Fn(&T, &T) -> Ordering;
+
+/// One key of a multi-key sort, used with `DBView::order_by_keys`
+pub struct SortKey<T> {
+    compare: Box<Comparator<T>>,
+}
+
+impl<T> SortKey<T> {
+    /// Sorts entries by `key`, smallest first
+    pub fn asc<K, F>(key: F) -> SortKey<T>
+        where K: Ord,
+              F: Fn(&T) -> K + 'static
+    {
+        SortKey { compare: Box::new(move |a, b| key(a).cmp(&key(b))) }
+    }
+
+    /// Sorts entries by `key`, largest first
+    pub fn desc<K, F>(key: F) -> SortKey<T>
+        where K: Ord,
+              F: Fn(&T) -> K + 'static
+    {
+        SortKey { compare: Box::new(move |a, b| key(b).cmp(&key(a))) }
+    }
+}
+
+impl<T> fmt::Debug for SortKey<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SortKey")
+    }
 }
 
 /// Filters a DBView using the the given predicate.
-pub fn filter_one<T, F>(view: &DBView<T>, predicate: F) -> DBView< T>
+pub fn filter_one<'a, T, F>(view: &DBView<'a, T>, predicate: F) -> DBView<'a, T>
     where F: Fn(&T) -> bool
 {
-    unimplemented!()
+    view.select_where(predicate)
 }
 
 /// Filters two DBView structs using the same predicate, producing two separate results. This is
 /// the moral equivalent of doing the two filters separately.
-pub fn filter_two<T, F>(view_a: &DBView<T>,
-                        view_b: &DBView<T>,
-                        predicate: F)
-                        -> (DBView<T>, DBView<T>)
+pub fn filter_two<'a, 'b, T, F>(view_a: &DBView<'a, T>,
+                                view_b: &DBView<'b, T>,
+                                predicate: F)
+                                -> (DBView<'a, T>, DBView<'b, T>)
     where F: Fn(&T) -> bool
 {
-    unimplemented!()
+    (view_a.select_where(&predicate), view_b.select_where(&predicate))
 }
 
 impl<T> DB<T> {
     /// Creates a DB from the given list of entries
     pub fn new(data: Vec<T>) -> DB<T> {
-        unimplemented!()
+        let ids = (0..data.len() as u64).map(RowId).collect();
+        DB::from_rows(data.len() as u64, ids, data)
     }
 
     /// Creates a new DBView containing all entries in `self` which satisfy `predicate`
-    pub fn select_where<F>(&self, predicate: F) -> DBView<T>
+    pub fn select_where<F>(&self, predicate: F) -> DBView<'_, T>
         where F: Fn(&T) -> bool
     {
-        unimplemented!()
+        DBView { entries: self.rows().filter(|&(_, t)| predicate(t)).collect() }
     }
 
     /// Creates a new DBView containing all entries in `self` which satisfy `predicate`
-    pub fn select_where_mut<F>(&mut self, predicate: F) -> DBViewMut<T>
+    pub fn select_where_mut<F>(&mut self, predicate: F) -> DBViewMut<'_, T>
         where F: Fn(&T) -> bool
     {
-        unimplemented!()
+        self.view_mut_where(predicate)
     }
 
     /// Returns a DBView consisting on the entirety of `self`
-    pub fn as_view(&self) -> DBView<T> {
-        unimplemented!()
+    pub fn as_view(&self) -> DBView<'_, T> {
+        DBView { entries: self.rows().collect() }
     }
 
     /// Returns a DBView consisting on the entirety of `self`
-    pub fn as_view_mut(&mut self) -> DBViewMut<T> {
-        unimplemented!()
+    pub fn as_view_mut(&mut self) -> DBViewMut<'_, T> {
+        self.view_mut_where(|_| true)
     }
 
     /// Returns the number of entries in the DB
     pub fn len(&self) -> usize {
-        unimplemented!()
+        self.data.len()
+    }
+
+    /// Returns true if the DB has no entries
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Adds `entry` to the end of `self`, returning the id it was given
+    pub fn insert(&mut self, entry: T) -> RowId {
+        self.sync_edits();
+        let id = RowId(self.next_id);
+        if let Some(ref mut wal) = self.wal {
+            wal.inserted(id, &entry);
+        }
+        self.next_id += 1;
+        for index in self.indexes.values_mut() {
+            index.insert(id, &entry);
+        }
+        if let Some(ref mut history) = self.history {
+            history.inserted(id, &entry);
+            history.end_step();
+        }
+        self.data.push(entry);
+        self.ids.push(id);
+        id
+    }
+
+    /// Removes the entry with the given id, if it is still in `self`
+    pub fn remove(&mut self, id: RowId) -> Option<T> {
+        self.sync_edits();
+        let i = self.position(id)?;
+        if let Some(ref mut wal) = self.wal {
+            wal.removed(id);
+        }
+        for index in self.indexes.values_mut() {
+            index.remove(id);
+        }
+        if let Some(ref mut history) = self.history {
+            history.removed(id, &self.data[i]);
+            history.end_step();
+        }
+        self.ids.remove(i);
+        Some(self.data.remove(i))
+    }
+
+    /// Removes all entries in `self` which satisfy `predicate`, returning them in their original
+    /// order. The entries which remain keep their relative order as well.
+    pub fn remove_where<F>(&mut self, predicate: F) -> Vec<T>
+        where F: Fn(&T) -> bool
+    {
+        self.remove_rows_where(predicate).into_iter().map(|(_, t)| t).collect()
+    }
+
+    /// Keeps only the entries in `self` which satisfy `predicate`, dropping the rest
+    pub fn retain<F>(&mut self, predicate: F)
+        where F: Fn(&T) -> bool
+    {
+        self.remove_where(|t| !predicate(t));
+    }
+
+    /// Returns the entry with the given id, if it is still in `self`
+    pub fn get(&self, id: RowId) -> Option<&T> {
+        self.position(id).map(|i| &self.data[i])
+    }
+
+    /// Returns the entry with the given id mutably, if it is still in `self`
+    pub fn get_mut(&mut self, id: RowId) -> Option<&mut T> {
+        self.sync_edits();
+        let i = self.position(id)?;
+        if self.tracks_edits() {
+            self.dirty.insert(id);
+        }
+        if let Some(ref mut history) = self.history {
+            history.editing(id, &self.data[i]);
+        }
+        Some(&mut self.data[i])
+    }
+
+    /// Returns true if the entry with the given id is still in `self`
+    pub fn contains(&self, id: RowId) -> bool {
+        self.position(id).is_some()
+    }
+
+    /// Returns an iterator over the entries of `self`
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { inner: self.data.iter() }
+    }
+
+    /// Returns an iterator over mutable references to the entries of `self`
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        self.sync_edits();
+        if self.tracks_edits() {
+            self.dirty.extend(self.ids.iter().cloned());
+        }
+        if let Some(ref mut history) = self.history {
+            for (&id, entry) in self.ids.iter().zip(&self.data) {
+                history.editing(id, entry);
+            }
+        }
+        IterMut { inner: self.data.iter_mut() }
+    }
+
+    /// Makes a DB of `data`, whose entries have the ids `ids`, which the caller has checked are
+    /// increasing and below `next_id`
+    pub(crate) fn from_rows(next_id: u64, ids: Vec<RowId>, data: Vec<T>) -> DB<T> {
+        DB {
+            data,
+            ids,
+            next_id,
+            indexes: BTreeMap::new(),
+            dirty: BTreeSet::new(),
+            history: None,
+            wal: None,
+        }
+    }
+
+    /// Like `remove_where`, but also returns the ids the entries had
+    pub(crate) fn remove_rows_where<F>(&mut self, predicate: F) -> Vec<(RowId, T)>
+        where F: Fn(&T) -> bool
+    {
+        self.sync_edits();
+        if let Some(ref mut wal) = self.wal {
+            wal.begin();
+        }
+        let mut removed = Vec::new();
+        let mut kept = Vec::with_capacity(self.data.len());
+        let mut kept_ids = Vec::with_capacity(self.ids.len());
+        for (id, entry) in self.ids.drain(..).zip(self.data.drain(..)) {
+            if predicate(&entry) {
+                if let Some(ref mut wal) = self.wal {
+                    wal.removed(id);
+                }
+                for index in self.indexes.values_mut() {
+                    index.remove(id);
+                }
+                if let Some(ref mut history) = self.history {
+                    history.removed(id, &entry);
+                }
+                removed.push((id, entry));
+            } else {
+                kept.push(entry);
+                kept_ids.push(id);
+            }
+        }
+        self.data = kept;
+        self.ids = kept_ids;
+        if let Some(ref mut wal) = self.wal {
+            wal.commit();
+        }
+        if let Some(ref mut history) = self.history {
+            history.end_step();
+        }
+        removed
+    }
+
+    /// Puts back an entry which was removed, with the id and position among the other entries it
+    /// had before
+    pub(crate) fn restore(&mut self, id: RowId, entry: T) {
+        self.sync_edits();
+        let i = self.ids.binary_search(&id).expect_err("restored id is still in use");
+        if let Some(ref mut wal) = self.wal {
+            wal.inserted(id, &entry);
+        }
+        for index in self.indexes.values_mut() {
+            index.insert(id, &entry);
+        }
+        if let Some(ref mut history) = self.history {
+            history.inserted(id, &entry);
+            history.end_step();
+        }
+        self.ids.insert(i, id);
+        self.data.insert(i, entry);
+    }
+
+    fn position(&self, id: RowId) -> Option<usize> {
+        self.ids.binary_search(&id).ok()
+    }
+
+    fn rows(&self) -> impl Iterator<Item = (RowId, &T)> {
+        self.ids.iter().cloned().zip(self.data.iter())
+    }
+
+    fn view_mut_where<F>(&mut self, predicate: F) -> DBViewMut<'_, T>
+        where F: Fn(&T) -> bool
+    {
+        self.sync_edits();
+        let track = self.tracks_edits();
+        let entries: Vec<_> = self.ids
+            .iter()
+            .cloned()
+            .zip(self.data.iter_mut())
+            .filter(|(_, t)| predicate(t))
+            .collect();
+        if track {
+            self.dirty.extend(entries.iter().map(|&(id, _)| id));
+        }
+        if let Some(ref mut history) = self.history {
+            for &(id, ref entry) in &entries {
+                history.editing(id, entry);
+            }
+        }
+        DBViewMut { entries }
+    }
+
+    /// Returns true if `self` needs to know which entries may have been changed in place
+    fn tracks_edits(&self) -> bool {
+        !self.indexes.is_empty() || self.history.is_some() || self.wal.is_some()
+    }
+
+    /// Brings everything which depends on the contents of entries up to date with any changes
+    /// made through mutable references since the last call. Every `&mut self` method calls this
+    /// first, since holding `&mut self` means no such references are still alive.
+    fn sync_edits(&mut self) {
+        if let Some(ref mut wal) = self.wal {
+            wal.begin();
+        }
+        for id in mem::take(&mut self.dirty) {
+            if let Some(i) = self.position(id) {
+                for index in self.indexes.values_mut() {
+                    index.remove(id);
+                    index.insert(id, &self.data[i]);
+                }
+                if let Some(ref mut history) = self.history {
+                    history.edited(id, &self.data[i]);
+                }
+                if let Some(ref mut wal) = self.wal {
+                    wal.edited(id, &self.data[i]);
+                }
+            }
+        }
+        if let Some(ref mut wal) = self.wal {
+            wal.commit();
+        }
+        if let Some(ref mut history) = self.history {
+            history.end_step();
+        }
     }
 }
 
-impl<T> DBView<T> {
+impl<'a, T> DBView<'a, T> {
     /// Creates a new DBView containing all entries in `self` which satisfy `predicate`
-    pub fn select_where<F>(&self, predicate: F) -> DBView<T>
+    pub fn select_where<F>(&self, predicate: F) -> DBView<'a, T>
         where F: Fn(&T) -> bool
     {
-        unimplemented!()
+        DBView {
+            entries: self.entries.iter().cloned().filter(|&(_, t)| predicate(t)).collect(),
+        }
+    }
+
+    /// Creates a new DBView with the entries of `self` sorted by `key`, smallest first. The sort is
+    /// stable, so entries with equal keys keep their relative order.
+    pub fn order_by<K, F>(&self, key: F) -> DBView<'a, T>
+        where K: Ord,
+              F: Fn(&T) -> K
+    {
+        self.sorted(|a, b| key(a).cmp(&key(b)))
+    }
+
+    /// Creates a new DBView with the entries of `self` sorted by `key`, largest first. The sort is
+    /// stable, so entries with equal keys keep their relative order.
+    pub fn order_by_desc<K, F>(&self, key: F) -> DBView<'a, T>
+        where K: Ord,
+              F: Fn(&T) -> K
+    {
+        self.sorted(|a, b| key(b).cmp(&key(a)))
+    }
+
+    /// Creates a new DBView with the entries of `self` sorted by each of `keys` in turn: later keys
+    /// only break ties left by earlier ones. The sort is stable.
+    pub fn order_by_keys(&self, keys: &[SortKey<T>]) -> DBView<'a, T> {
+        self.sorted(|a, b| {
+            keys.iter()
+                .map(|k| (k.compare)(a, b))
+                .find(|&o| o != Ordering::Equal)
+                .unwrap_or(Ordering::Equal)
+        })
+    }
+
+    /// Creates a new DBView containing at most the first `n` entries of `self`
+    pub fn limit(&self, n: usize) -> DBView<'a, T> {
+        DBView { entries: self.entries.iter().cloned().take(n).collect() }
+    }
+
+    /// Creates a new DBView containing all but the first `n` entries of `self`
+    pub fn offset(&self, n: usize) -> DBView<'a, T> {
+        DBView { entries: self.entries.iter().cloned().skip(n).collect() }
+    }
+
+    /// Creates a new DBView of the part of each entry of `self` picked out by `field`, usually a
+    /// single field as in `view.project(|p| &p.name)`. Each part keeps the id of its entry, and
+    /// borrows from the underlying DB rather than from `self`.
+    pub fn project<U, F>(&self, field: F) -> DBView<'a, U>
+        where F: Fn(&'a T) -> &'a U
+    {
+        DBView { entries: self.entries.iter().map(|&(id, t)| (id, field(t))).collect() }
     }
 
     /// Returns the number of entries in the DBView
     pub fn len(&self) -> usize {
-        unimplemented!()
+        self.entries.len()
+    }
+
+    /// Returns true if the DBView has no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn sorted<F>(&self, compare: F) -> DBView<'a, T>
+        where F: Fn(&T, &T) -> Ordering
+    {
+        let mut entries = self.entries.clone();
+        entries.sort_by(|&(_, a), &(_, b)| compare(a, b));
+        DBView { entries }
+    }
+
+    /// Returns an iterator over the entries of `self`. The references it yields live as long as
+    /// the underlying DB, not just as long as the view.
+    pub fn iter<'v>(&'v self) -> ViewIter<'v, 'a, T> {
+        ViewIter { inner: self.entries.iter() }
+    }
+
+    /// Returns an iterator over the entries of `self` along with their ids in the underlying DB
+    pub fn rows<'v>(&'v self) -> Rows<'v, 'a, T> {
+        Rows { inner: self.entries.iter() }
     }
 }
 
-impl<T> DBViewMut<T> {
+impl<'a, T> DBViewMut<'a, T> {
     /// Creates a new DBView containing all entries in `self` which satisfy `predicate`
-    pub fn select_where_mut<F>(self, predicate: F) -> DBViewMut<T>
+    pub fn select_where_mut<F>(self, predicate: F) -> DBViewMut<'a, T>
         where F: Fn(&T) -> bool
     {
-        unimplemented!()
+        DBViewMut {
+            entries: self.entries.into_iter().filter(|(_, t)| predicate(t)).collect(),
+        }
+    }
+
+    /// Converts `self` into a DBViewMut of the part of each entry picked out by `field`, usually
+    /// a single field as in `view.project_mut(|p| &mut p.name)`. Each part keeps the id of its
+    /// entry.
+    pub fn project_mut<U, F>(self, mut field: F) -> DBViewMut<'a, U>
+        where F: FnMut(&'a mut T) -> &'a mut U
+    {
+        DBViewMut { entries: self.entries.into_iter().map(|(id, t)| (id, field(t))).collect() }
     }
 
     /// Returns the number of entries in the DBView
     pub fn len(&self) -> usize {
-        unimplemented!()
-    }
-}
-
-// Bonus A
-//
-// impl<T> IntoIterator for DB<T> {
-//     type Item = T;
-//     // TODO
-// }
-//
-// impl<T> IntoIterator for &DB<T> {
-//     type Item = &T;
-//     // TODO
-// }
-//
-// impl<T> IntoIterator for &mut DB<T> {
-//     type Item = &mut T;
-//     // TODO
-// }
-//
-// impl<T> IntoIterator for DBView<T> {
-//     type Item = &T;
-//     // TODO
-// }
-//
-// impl<T> IntoIterator for DBViewMut<T> {
-//     type Item = &mut T;
-//     // TODO
-// }
+        self.entries.len()
+    }
+
+    /// Returns true if the DBView has no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns an iterator over shared references to the entries of `self`
+    pub fn iter<'v>(&'v self) -> ViewMutIter<'v, 'a, T> {
+        ViewMutIter { inner: self.entries.iter() }
+    }
+
+    /// Returns an iterator over mutable references to the entries of `self`. The references are
+    /// reborrowed from the view, so they cannot outlive it.
+    pub fn iter_mut<'v>(&'v mut self) -> ViewMutIterMut<'v, 'a, T> {
+        ViewMutIterMut { inner: self.entries.iter_mut() }
+    }
+
+    /// Returns an iterator over mutable references to the entries of `self` along with their ids
+    /// in the underlying DB
+    pub fn rows_mut<'v>(&'v mut self) -> RowsMut<'v, 'a, T> {
+        RowsMut { inner: self.entries.iter_mut() }
+    }
+
+    /// Converts `self` into an iterator over its entries along with their ids in the underlying DB
+    pub fn into_rows(self) -> IntoRowsMut<'a, T> {
+        IntoRowsMut { inner: self.entries.into_iter() }
+    }
+}
+
+/// An owning iterator over the entries of a DB
+#[derive(Debug)]
+pub struct IntoIter<T> {
+    inner: vec::IntoIter<T>,
+}
+
+forward_iterator!(IntoIter<; T>, T, |t| t);
+
+/// An iterator over shared references to the entries of a DB
+#[derive(Debug)]
+pub struct Iter<'a, T: 'a> {
+    inner: slice::Iter<'a, T>,
+}
+
+forward_iterator!(Iter<'a; T>, &'a T, |t| t);
+
+/// An iterator over mutable references to the entries of a DB
+#[derive(Debug)]
+pub struct IterMut<'a, T: 'a> {
+    inner: slice::IterMut<'a, T>,
+}
+
+forward_iterator!(IterMut<'a; T>, &'a mut T, |t| t);
+
+/// An owning iterator over the entries of a DBView
+#[derive(Debug)]
+pub struct ViewIntoIter<'a, T: 'a> {
+    inner: vec::IntoIter<(RowId, &'a T)>,
+}
+
+forward_iterator!(ViewIntoIter<'a; T>, &'a T, |(_, t)| t);
+
+/// A borrowing iterator over the entries of a DBView
+#[derive(Debug)]
+pub struct ViewIter<'v, 'a: 'v, T: 'a> {
+    inner: slice::Iter<'v, (RowId, &'a T)>,
+}
+
+forward_iterator!(ViewIter<'v, 'a; T>, &'a T, |&(_, t)| t);
+
+/// An iterator over the entries of a DBView along with their ids
+#[derive(Debug)]
+pub struct Rows<'v, 'a: 'v, T: 'a> {
+    inner: slice::Iter<'v, (RowId, &'a T)>,
+}
+
+forward_iterator!(Rows<'v, 'a; T>, (RowId, &'a T), |&(id, t)| (id, t));
+
+/// An owning iterator over the entries of a DBViewMut
+#[derive(Debug)]
+pub struct ViewMutIntoIter<'a, T: 'a> {
+    inner: vec::IntoIter<(RowId, &'a mut T)>,
+}
+
+forward_iterator!(ViewMutIntoIter<'a; T>, &'a mut T, |(_, t)| t);
+
+/// An owning iterator over the entries of a DBViewMut along with their ids
+#[derive(Debug)]
+pub struct IntoRowsMut<'a, T: 'a> {
+    inner: vec::IntoIter<(RowId, &'a mut T)>,
+}
+
+forward_iterator!(IntoRowsMut<'a; T>, (RowId, &'a mut T), |row| row);
+
+/// An iterator over shared references to the entries of a DBViewMut
+#[derive(Debug)]
+pub struct ViewMutIter<'v, 'a: 'v, T: 'a> {
+    inner: slice::Iter<'v, (RowId, &'a mut T)>,
+}
+
+forward_iterator!(ViewMutIter<'v, 'a; T>, &'v T, |(_, t)| &**t);
+
+/// An iterator over mutable references to the entries of a DBViewMut
+#[derive(Debug)]
+pub struct ViewMutIterMut<'v, 'a: 'v, T: 'a> {
+    inner: slice::IterMut<'v, (RowId, &'a mut T)>,
+}
+
+forward_iterator!(ViewMutIterMut<'v, 'a; T>, &'v mut T, |(_, t)| &mut **t);
+
+/// An iterator over mutable references to the entries of a DBViewMut along with their ids
+#[derive(Debug)]
+pub struct RowsMut<'v, 'a: 'v, T: 'a> {
+    inner: slice::IterMut<'v, (RowId, &'a mut T)>,
+}
+
+forward_iterator!(RowsMut<'v, 'a; T>, (RowId, &'v mut T), |(id, t)| (*id, &mut **t));
+
+impl<T> IntoIterator for DB<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(mut self) -> IntoIter<T> {
+        self.sync_edits();
+        IntoIter { inner: mem::take(&mut self.data).into_iter() }
+    }
+}
+
+/// Edits made in place to a durable DB which haven't been logged yet are logged when it's dropped
+impl<T> Drop for DB<T> {
+    fn drop(&mut self) {
+        // A failed write panics, which would abort if already unwinding
+        if self.wal.is_some() && !thread::panicking() {
+            self.sync_edits();
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a DB<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut DB<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+impl<'a, T> IntoIterator for DBView<'a, T> {
+    type Item = &'a T;
+    type IntoIter = ViewIntoIter<'a, T>;
+
+    fn into_iter(self) -> ViewIntoIter<'a, T> {
+        ViewIntoIter { inner: self.entries.into_iter() }
+    }
+}
+
+impl<'v, 'a, T> IntoIterator for &'v DBView<'a, T> {
+    type Item = &'a T;
+    type IntoIter = ViewIter<'v, 'a, T>;
+
+    fn into_iter(self) -> ViewIter<'v, 'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for DBViewMut<'a, T> {
+    type Item = &'a mut T;
+    type IntoIter = ViewMutIntoIter<'a, T>;
+
+    fn into_iter(self) -> ViewMutIntoIter<'a, T> {
+        ViewMutIntoIter { inner: self.entries.into_iter() }
+    }
+}
+
+impl<'v, 'a, T> IntoIterator for &'v DBViewMut<'a, T> {
+    type Item = &'v T;
+    type IntoIter = ViewMutIter<'v, 'a, T>;
+
+    fn into_iter(self) -> ViewMutIter<'v, 'a, T> {
+        self.iter()
+    }
+}
+
+impl<'v, 'a, T> IntoIterator for &'v mut DBViewMut<'a, T> {
+    type Item = &'v mut T;
+    type IntoIter = ViewMutIterMut<'v, 'a, T>;
+
+    fn into_iter(self) -> ViewMutIterMut<'v, 'a, T> {
+        self.iter_mut()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for DB<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DB")
+            .field("data", &self.data)
+            .field("indexes", &self.indexes.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Two DBs are equal if they hold equal entries under the same ids. Indexes are not compared.
+impl<T: PartialEq> PartialEq for DB<T> {
+    fn eq(&self, other: &DB<T>) -> bool {
+        self.ids == other.ids && self.data == other.data
+    }
+}
+
+impl<T: Eq> Eq for DB<T> {}
+
+/// Two views are equal if they hold equal entries in the same order, whichever rows they're from
+impl<'a, T: PartialEq> PartialEq for DBView<'a, T> {
+    fn eq(&self, other: &DBView<'a, T>) -> bool {
+        self.entries.len() == other.entries.len() &&
+        self.entries.iter().zip(&other.entries).all(|(&(_, a), &(_, b))| a == b)
+    }
+}
+
+impl<'a, T: Eq> Eq for DBView<'a, T> {}
+
+/// Two views are equal if they hold equal entries in the same order, whichever rows they're from
+impl<'a, T: PartialEq> PartialEq for DBViewMut<'a, T> {
+    fn eq(&self, other: &DBViewMut<'a, T>) -> bool {
+        self.entries.len() == other.entries.len() &&
+        self.entries.iter().zip(&other.entries).all(|((_, a), (_, b))| a == b)
+    }
+}
+
+impl<'a, T: Eq> Eq for DBViewMut<'a, T> {}
+
+impl<T> Extend<T> for DB<T> {
+    fn extend<I>(&mut self, iter: I)
+        where I: IntoIterator<Item = T>
+    {
+        for entry in iter {
+            self.insert(entry);
+        }
+    }
+}
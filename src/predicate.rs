@@ -0,0 +1,341 @@
+//! Predicates which can be combined, described and shared, for use with `select_where` and friends
+//!
+//! Any `Fn(&T) -> bool` is a predicate, so predicates can be built up from plain closures and
+//! functions. The select functions take closures, so that their argument types can be inferred,
+//! and a predicate is passed to them with `as_fn`, as in `db.select_where(p.and(q).as_fn())`.
+
+use std::any::type_name;
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// A test on entries of type `T`
+pub trait Predicate<T> {
+    /// Returns true if `entry` satisfies the predicate
+    fn test(&self, entry: &T) -> bool;
+
+    /// Writes a human readable description of the predicate. This is what the `Debug` impls of
+    /// the combinators print.
+    fn describe(&self, f: &mut fmt::Formatter) -> fmt::Result;
+
+    /// Combines `self` and `other` into a predicate satisfied when both are
+    fn and<P>(self, other: P) -> And<T, Self, P>
+        where Self: Sized,
+              P: Predicate<T>
+    {
+        And { a: self, b: other, entry: PhantomData }
+    }
+
+    /// Combines `self` and `other` into a predicate satisfied when either is
+    fn or<P>(self, other: P) -> Or<T, Self, P>
+        where Self: Sized,
+              P: Predicate<T>
+    {
+        Or { a: self, b: other, entry: PhantomData }
+    }
+
+    /// Negates `self`
+    fn not(self) -> Not<T, Self>
+        where Self: Sized
+    {
+        Not { inner: self, entry: PhantomData }
+    }
+
+    /// Borrows `self` as a closure, for the select functions and anything else taking a
+    /// `Fn(&T) -> bool`
+    fn as_fn(&self) -> impl Fn(&T) -> bool + '_
+        where Self: Sized
+    {
+        move |entry| self.test(entry)
+    }
+
+    /// Borrows `self` as a predicate, so it can be used more than once
+    fn by_ref(&self) -> ByRef<'_, T, Self>
+        where Self: Sized
+    {
+        ByRef { inner: self, entry: PhantomData }
+    }
+
+    /// Puts `self` behind a reference counted pointer, so it can be cloned cheaply and sent to
+    /// other threads, and so predicates of different types can be stored together
+    fn shared(self) -> SharedPredicate<T>
+        where Self: Sized + Send + Sync + 'static
+    {
+        SharedPredicate { inner: Arc::new(self) }
+    }
+}
+
+impl<T, F> Predicate<T> for F
+    where F: Fn(&T) -> bool
+{
+    fn test(&self, entry: &T) -> bool {
+        self(entry)
+    }
+
+    /// Closures can't describe themselves, so this falls back on the name of their type, which
+    /// for a function is its path.
+    fn describe(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(type_name::<F>())
+    }
+}
+
+/// Implements `Debug` for a predicate type in terms of `Predicate::describe`
+macro_rules! debug_via_describe {
+    ($name:ident < $($lt:lifetime,)* T $(, $p:ident)* >) => {
+        impl<$($lt,)* T $(, $p: Predicate<T>)*> fmt::Debug for $name<$($lt,)* T $(, $p)*>
+            where $name<$($lt,)* T $(, $p)*>: Predicate<T>
+        {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                self.describe(f)
+            }
+        }
+    }
+}
+
+/// Satisfied when both of two predicates are. Made by `Predicate::and`.
+pub struct And<T, A, B> {
+    a: A,
+    b: B,
+    entry: PhantomData<fn(&T)>,
+}
+
+impl<T, A: Predicate<T>, B: Predicate<T>> Predicate<T> for And<T, A, B> {
+    fn test(&self, entry: &T) -> bool {
+        self.a.test(entry) && self.b.test(entry)
+    }
+
+    fn describe(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "(")?;
+        self.a.describe(f)?;
+        write!(f, " AND ")?;
+        self.b.describe(f)?;
+        write!(f, ")")
+    }
+}
+
+impl<T, A: Clone, B: Clone> Clone for And<T, A, B> {
+    fn clone(&self) -> Self {
+        And { a: self.a.clone(), b: self.b.clone(), entry: PhantomData }
+    }
+}
+
+debug_via_describe!(And<T, A, B>);
+
+/// Satisfied when either of two predicates is. Made by `Predicate::or`.
+pub struct Or<T, A, B> {
+    a: A,
+    b: B,
+    entry: PhantomData<fn(&T)>,
+}
+
+impl<T, A: Predicate<T>, B: Predicate<T>> Predicate<T> for Or<T, A, B> {
+    fn test(&self, entry: &T) -> bool {
+        self.a.test(entry) || self.b.test(entry)
+    }
+
+    fn describe(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "(")?;
+        self.a.describe(f)?;
+        write!(f, " OR ")?;
+        self.b.describe(f)?;
+        write!(f, ")")
+    }
+}
+
+impl<T, A: Clone, B: Clone> Clone for Or<T, A, B> {
+    fn clone(&self) -> Self {
+        Or { a: self.a.clone(), b: self.b.clone(), entry: PhantomData }
+    }
+}
+
+debug_via_describe!(Or<T, A, B>);
+
+/// Satisfied when a predicate is not. Made by `Predicate::not` or `not`.
+pub struct Not<T, A> {
+    inner: A,
+    entry: PhantomData<fn(&T)>,
+}
+
+impl<T, A: Predicate<T>> Predicate<T> for Not<T, A> {
+    fn test(&self, entry: &T) -> bool {
+        !self.inner.test(entry)
+    }
+
+    fn describe(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "NOT ")?;
+        self.inner.describe(f)
+    }
+}
+
+impl<T, A: Clone> Clone for Not<T, A> {
+    fn clone(&self) -> Self {
+        Not { inner: self.inner.clone(), entry: PhantomData }
+    }
+}
+
+debug_via_describe!(Not<T, A>);
+
+/// A closure paired with a description of what it tests. Made by `named`.
+pub struct Named<T, F> {
+    description: String,
+    test: F,
+    entry: PhantomData<fn(&T)>,
+}
+
+impl<T, F: Fn(&T) -> bool> Predicate<T> for Named<T, F> {
+    fn test(&self, entry: &T) -> bool {
+        (self.test)(entry)
+    }
+
+    fn describe(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.description)
+    }
+}
+
+impl<T, F: Clone> Clone for Named<T, F> {
+    fn clone(&self) -> Self {
+        Named {
+            description: self.description.clone(),
+            test: self.test.clone(),
+            entry: PhantomData,
+        }
+    }
+}
+
+impl<T, F> fmt::Debug for Named<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.description)
+    }
+}
+
+/// A borrowed predicate. Made by `Predicate::by_ref`.
+pub struct ByRef<'p, T, P: ?Sized + 'p> {
+    inner: &'p P,
+    entry: PhantomData<fn(&T)>,
+}
+
+impl<'p, T, P: ?Sized> Clone for ByRef<'p, T, P> {
+    fn clone(&self) -> Self {
+        ByRef { inner: self.inner, entry: PhantomData }
+    }
+}
+
+impl<'p, T, P: Predicate<T> + ?Sized> Predicate<T> for ByRef<'p, T, P> {
+    fn test(&self, entry: &T) -> bool {
+        self.inner.test(entry)
+    }
+
+    fn describe(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.inner.describe(f)
+    }
+}
+
+debug_via_describe!(ByRef<'p, T, P>);
+
+/// A predicate of any type, shared behind a reference counted pointer. Made by
+/// `Predicate::shared`.
+pub struct SharedPredicate<T> {
+    inner: Arc<dyn Predicate<T> + Send + Sync>,
+}
+
+impl<T> Clone for SharedPredicate<T> {
+    fn clone(&self) -> Self {
+        SharedPredicate { inner: self.inner.clone() }
+    }
+}
+
+impl<T> Predicate<T> for SharedPredicate<T> {
+    fn test(&self, entry: &T) -> bool {
+        self.inner.test(entry)
+    }
+
+    fn describe(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.inner.describe(f)
+    }
+}
+
+debug_via_describe!(SharedPredicate<T>);
+
+/// Satisfied when every one of a list of predicates is. Made by `all_of`.
+pub struct AllOf<T> {
+    predicates: Vec<SharedPredicate<T>>,
+}
+
+impl<T> Predicate<T> for AllOf<T> {
+    fn test(&self, entry: &T) -> bool {
+        self.predicates.iter().all(|p| p.test(entry))
+    }
+
+    fn describe(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        describe_list(f, "ALL OF", &self.predicates)
+    }
+}
+
+impl<T> Clone for AllOf<T> {
+    fn clone(&self) -> Self {
+        AllOf { predicates: self.predicates.clone() }
+    }
+}
+
+debug_via_describe!(AllOf<T>);
+
+/// Satisfied when at least one of a list of predicates is. Made by `any_of`.
+pub struct AnyOf<T> {
+    predicates: Vec<SharedPredicate<T>>,
+}
+
+impl<T> Predicate<T> for AnyOf<T> {
+    fn test(&self, entry: &T) -> bool {
+        self.predicates.iter().any(|p| p.test(entry))
+    }
+
+    fn describe(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        describe_list(f, "ANY OF", &self.predicates)
+    }
+}
+
+impl<T> Clone for AnyOf<T> {
+    fn clone(&self) -> Self {
+        AnyOf { predicates: self.predicates.clone() }
+    }
+}
+
+debug_via_describe!(AnyOf<T>);
+
+fn describe_list<T>(f: &mut fmt::Formatter,
+                    name: &str,
+                    predicates: &[SharedPredicate<T>])
+                    -> fmt::Result {
+    write!(f, "{} [", name)?;
+    for (i, p) in predicates.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        p.describe(f)?;
+    }
+    write!(f, "]")
+}
+
+/// Makes a predicate satisfied when every one of `predicates` is. An empty list is always
+/// satisfied.
+pub fn all_of<T>(predicates: Vec<SharedPredicate<T>>) -> AllOf<T> {
+    AllOf { predicates }
+}
+
+/// Makes a predicate satisfied when at least one of `predicates` is. An empty list is never
+/// satisfied.
+pub fn any_of<T>(predicates: Vec<SharedPredicate<T>>) -> AnyOf<T> {
+    AnyOf { predicates }
+}
+
+/// Makes a predicate which negates `predicate`
+pub fn not<T, P: Predicate<T>>(predicate: P) -> Not<T, P> {
+    predicate.not()
+}
+
+/// Gives `test` a description, which is used when the predicate is printed
+pub fn named<T, F>(description: &str, test: F) -> Named<T, F>
+    where F: Fn(&T) -> bool
+{
+    Named { description: description.to_string(), test, entry: PhantomData }
+}
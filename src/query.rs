@@ -0,0 +1,389 @@
+//! Lazily planned queries over a DB
+//!
+//! Chaining `select_where` calls builds a new view at every step. A `Query` instead records the
+//! steps and runs them all in a single pass when the result is asked for, checking every filter on
+//! an entry before moving to the next and stopping as soon as a limit is reached. Filters which can
+//! be answered by an index are also planned: the most selective one is used to find candidate
+//! entries, so the DB is only scanned when no index applies.
+
+use std::cmp;
+use std::fmt;
+use std::hash::Hash;
+use std::ops::RangeBounds;
+use std::time::Instant;
+
+use explain::{Actual, Operation, Plan};
+use index::{HashIndex, OrderedIndex};
+use {DB, DBView, Predicate, RowId, SortKey};
+
+/// A query against a DB which is built up step by step and only run by `collect_view`. Made by
+/// `DB::query`.
+pub struct Query<'a, T: 'a> {
+    db: &'a DB<T>,
+    filters: Vec<Filter<'a, T>>,
+    order: Vec<SortKey<T>>,
+    offset: usize,
+    limit: Option<usize>,
+}
+
+enum Filter<'a, T: 'a> {
+    Predicate(Box<dyn Predicate<T> + 'a>),
+    Index(Box<dyn IndexFilter<'a, T> + 'a>),
+}
+
+impl<'a, T> Filter<'a, T> {
+    fn test(&self, entry: &T) -> bool {
+        match *self {
+            Filter::Predicate(ref p) => p.test(entry),
+            Filter::Index(ref i) => i.test(entry),
+        }
+    }
+}
+
+impl<'a, T> fmt::Debug for Filter<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Filter::Predicate(ref p) => p.describe(f),
+            Filter::Index(ref i) => i.describe(f),
+        }
+    }
+}
+
+/// A filter on the key of an index, which can either look its matches up in the index or be
+/// checked against single entries like any other predicate
+trait IndexFilter<'a, T> {
+    /// Returns the name of the index
+    fn index(&self) -> &str;
+
+    /// Returns how many entries the index says match, without looking at any of them
+    fn estimate(&self) -> usize;
+
+    /// Returns the matching entries, in the order of the DB
+    fn lookup(&self) -> Vec<(RowId, &'a T)>;
+
+    fn test(&self, entry: &T) -> bool;
+
+    fn describe(&self, f: &mut fmt::Formatter) -> fmt::Result;
+}
+
+struct EqFilter<'a, T: 'a, K: 'a> {
+    db: &'a DB<T>,
+    name: String,
+    index: &'a HashIndex<T, K>,
+    key: K,
+}
+
+impl<'a, T, K> IndexFilter<'a, T> for EqFilter<'a, T, K>
+    where T: 'static,
+          K: Hash + Eq + Clone + fmt::Debug + 'static
+{
+    fn index(&self) -> &str {
+        &self.name
+    }
+
+    fn estimate(&self) -> usize {
+        self.index.count(&self.key)
+    }
+
+    fn lookup(&self) -> Vec<(RowId, &'a T)> {
+        self.db.select_eq(&self.name, &self.key).entries
+    }
+
+    fn test(&self, entry: &T) -> bool {
+        self.index.key_of(entry) == self.key
+    }
+
+    fn describe(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} = {:?}", self.name, self.key)
+    }
+}
+
+struct RangeFilter<'a, T: 'a, K: 'a, R> {
+    db: &'a DB<T>,
+    name: String,
+    index: &'a OrderedIndex<T, K>,
+    range: R,
+}
+
+impl<'a, T, K, R> IndexFilter<'a, T> for RangeFilter<'a, T, K, R>
+    where T: 'static,
+          K: Ord + Clone + 'static,
+          R: RangeBounds<K> + Clone + fmt::Debug
+{
+    fn index(&self) -> &str {
+        &self.name
+    }
+
+    fn estimate(&self) -> usize {
+        self.index.count_range(&self.range)
+    }
+
+    fn lookup(&self) -> Vec<(RowId, &'a T)> {
+        let mut entries = self.db.select_range(&self.name, self.range.clone()).entries;
+        entries.sort_by_key(|&(id, _)| id);
+        entries
+    }
+
+    fn test(&self, entry: &T) -> bool {
+        self.range.contains(&self.index.key_of(entry))
+    }
+
+    fn describe(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} in {:?}", self.name, self.range)
+    }
+}
+
+impl<'a, T> fmt::Debug for Query<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Query")
+            .field("filters", &self.filters)
+            .field("order", &self.order)
+            .field("offset", &self.offset)
+            .field("limit", &self.limit)
+            .finish()
+    }
+}
+
+impl<T> DB<T> {
+    /// Starts a query against `self`. Nothing is done until `Query::collect_view` is called.
+    pub fn query(&self) -> Query<'_, T> {
+        Query { db: self, filters: Vec::new(), order: Vec::new(), offset: 0, limit: None }
+    }
+}
+
+impl<'a, T> Query<'a, T> {
+    /// Keeps only entries which satisfy `predicate`
+    pub fn filter<F>(self, predicate: F) -> Query<'a, T>
+        where F: Fn(&T) -> bool + 'a
+    {
+        self.filter_by(predicate)
+    }
+
+    /// Like `filter`, but takes any predicate, whose description then shows up in the query's
+    /// plan
+    pub fn filter_by<P>(mut self, predicate: P) -> Query<'a, T>
+        where P: Predicate<T> + 'a
+    {
+        self.filters.push(Filter::Predicate(Box::new(predicate)));
+        self
+    }
+
+    /// Sorts the results by `key`, smallest first. If this is called more than once, later keys
+    /// only break ties left by earlier ones. The sort is stable.
+    pub fn order_by<K, F>(mut self, key: F) -> Query<'a, T>
+        where K: Ord,
+              F: Fn(&T) -> K + 'static
+    {
+        self.order.push(SortKey::asc(key));
+        self
+    }
+
+    /// Sorts the results by `key`, largest first. Combines with `order_by` as it does with itself.
+    pub fn order_by_desc<K, F>(mut self, key: F) -> Query<'a, T>
+        where K: Ord,
+              F: Fn(&T) -> K + 'static
+    {
+        self.order.push(SortKey::desc(key));
+        self
+    }
+
+    /// Skips the first `n` results. Calling this again replaces the earlier offset.
+    pub fn offset(mut self, n: usize) -> Query<'a, T> {
+        self.offset = n;
+        self
+    }
+
+    /// Keeps at most `n` results, after any offset. Calling this again replaces the earlier limit.
+    pub fn limit(mut self, n: usize) -> Query<'a, T> {
+        self.limit = Some(n);
+        self
+    }
+
+    /// Runs the query, producing an ordinary DBView. Without an `order_by`, results are in the
+    /// order of the DB whatever index was used to find them.
+    pub fn collect_view(self) -> DBView<'a, T> {
+        DBView { entries: self.run(false).0 }
+    }
+
+    /// Returns the plan the query would be run with, without running it
+    pub fn explain(&self) -> Plan {
+        let source = self.source();
+        let mut plan = match source {
+            Some(i) => {
+                let index = self.index_filter(i);
+                let operation = Operation::IndexLookup {
+                    index: index.index().to_string(),
+                    condition: format!("{:?}", self.filters[i]),
+                };
+                Plan::new(operation, index.estimate(), None)
+            }
+            None => Plan::new(Operation::Scan, self.db.len(), None),
+        };
+        for (i, filter) in self.filters.iter().enumerate() {
+            if Some(i) == source {
+                continue;
+            }
+            let estimate = match *filter {
+                Filter::Index(ref index) => cmp::min(plan.estimated_rows(), index.estimate()),
+                Filter::Predicate(_) => plan.estimated_rows(),
+            };
+            let operation = Operation::Filter { condition: format!("{:?}", filter) };
+            plan = Plan::new(operation, estimate, Some(plan));
+        }
+        if !self.order.is_empty() {
+            let estimate = plan.estimated_rows();
+            plan = Plan::new(Operation::Sort { keys: self.order.len() }, estimate, Some(plan));
+        }
+        if self.offset > 0 {
+            let estimate = plan.estimated_rows().saturating_sub(self.offset);
+            plan = Plan::new(Operation::Offset(self.offset), estimate, Some(plan));
+        }
+        if let Some(limit) = self.limit {
+            let estimate = cmp::min(plan.estimated_rows(), limit);
+            plan = Plan::new(Operation::Limit(limit), estimate, Some(plan));
+        }
+        plan
+    }
+
+    /// Runs the query and returns its plan, filled in with how many entries each step actually
+    /// passed on and how long it took. Timing every step slows the query down, so the times are
+    /// best compared with each other rather than with `collect_view`.
+    pub fn explain_analyze(&self) -> Plan {
+        let actual = self.run(true).1;
+        self.explain().with_actual(&actual)
+    }
+
+    /// Runs the query, counting the entries passed on by each step of its plan, from the innermost
+    /// outwards. Steps are only timed if `timed` is true.
+    fn run(&self, timed: bool) -> (Vec<(RowId, &'a T)>, Vec<Actual>) {
+        let source = self.source();
+        let residual: Vec<&Filter<'a, T>> = self.filters
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| Some(i) != source)
+            .map(|(_, filter)| filter)
+            .collect();
+        let mut actual = vec![Actual::default(); 1 + residual.len()];
+
+        let mut candidates: Box<dyn Iterator<Item = (RowId, &'a T)>> = match source {
+            Some(i) => {
+                let entries = time(timed, &mut actual[0], || self.index_filter(i).lookup());
+                Box::new(entries.into_iter())
+            }
+            None => Box::new(self.db.rows()),
+        };
+        // Without an ordering, nothing after the first `offset + limit` matches can be returned
+        let wanted = match self.limit {
+            Some(limit) if self.order.is_empty() => self.offset.saturating_add(limit),
+            _ => usize::MAX,
+        };
+        let mut entries = Vec::new();
+        while entries.len() < wanted {
+            let (id, entry) = match time(timed, &mut actual[0], || candidates.next()) {
+                Some(candidate) => candidate,
+                None => break,
+            };
+            actual[0].rows += 1;
+            let kept = residual.iter().zip(&mut actual[1..]).all(|(filter, actual)| {
+                let kept = time(timed, actual, || filter.test(entry));
+                actual.rows += kept as usize;
+                kept
+            });
+            if kept {
+                entries.push((id, entry));
+            }
+        }
+
+        if !self.order.is_empty() {
+            let mut sort = Actual::default();
+            entries = time(timed, &mut sort, || {
+                DBView { entries }.order_by_keys(&self.order).entries
+            });
+            sort.rows = entries.len();
+            actual.push(sort);
+        }
+        if self.offset > 0 {
+            let mut offset = Actual::default();
+            time(timed, &mut offset, || entries.drain(..cmp::min(self.offset, entries.len())));
+            offset.rows = entries.len();
+            actual.push(offset);
+        }
+        if let Some(limit) = self.limit {
+            let mut truncate = Actual::default();
+            time(timed, &mut truncate, || entries.truncate(limit));
+            truncate.rows = entries.len();
+            actual.push(truncate);
+        }
+        (entries, actual)
+    }
+
+    /// Picks the index filter expected to match the fewest entries, if there are any
+    fn source(&self) -> Option<usize> {
+        self.filters
+            .iter()
+            .enumerate()
+            .filter_map(|(i, filter)| match *filter {
+                Filter::Index(ref index) => Some((index.estimate(), i)),
+                Filter::Predicate(_) => None,
+            })
+            .min()
+            .map(|(_, i)| i)
+    }
+
+    fn index_filter(&self, i: usize) -> &dyn IndexFilter<'a, T> {
+        match self.filters[i] {
+            Filter::Index(ref index) => &**index,
+            Filter::Predicate(_) => unreachable!("only index filters are sources"),
+        }
+    }
+}
+
+/// Calls `f`, adding the time it took to `actual` if `timed` is true
+fn time<R, F: FnOnce() -> R>(timed: bool, actual: &mut Actual, f: F) -> R {
+    if !timed {
+        return f();
+    }
+    let start = Instant::now();
+    let result = f();
+    actual.time += start.elapsed();
+    result
+}
+
+impl<'a, T: 'static> Query<'a, T> {
+    /// Keeps only entries whose key in the hash index `index` equals `key`
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no hash index called `index` with keys of type `K`.
+    pub fn filter_eq<K>(mut self, index: &str, key: K) -> Query<'a, T>
+        where K: Hash + Eq + Clone + fmt::Debug + 'static
+    {
+        let filter = EqFilter {
+            db: self.db,
+            name: index.to_string(),
+            index: self.db.typed_index(index),
+            key,
+        };
+        self.filters.push(Filter::Index(Box::new(filter)));
+        self
+    }
+
+    /// Keeps only entries whose key in the ordered index `index` lies in `range`
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no ordered index called `index` with keys of type `K`.
+    pub fn filter_range<K, R>(mut self, index: &str, range: R) -> Query<'a, T>
+        where K: Ord + Clone + 'static,
+              R: RangeBounds<K> + Clone + fmt::Debug + 'a
+    {
+        let filter = RangeFilter {
+            db: self.db,
+            name: index.to_string(),
+            index: self.db.typed_index(index),
+            range,
+        };
+        self.filters.push(Filter::Index(Box::new(filter)));
+        self
+    }
+}
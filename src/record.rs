@@ -0,0 +1,314 @@
+//! Records: entry types which describe their fields, so they can be used without knowing the type
+//!
+//! The easiest way to make a struct a record is to define it inside `record!`, which implements
+//! `Record` and `FieldAccess` for it from its field declarations.
+
+use std::convert::TryFrom;
+
+use {TypeError, Value};
+
+/// The type of the values a field holds
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FieldType {
+    Bool,
+    Int,
+    Float,
+    Str,
+    Bytes,
+    /// A list of values of any types
+    List,
+}
+
+/// The name and type of one field of a record
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Field {
+    pub name: String,
+    pub ty: FieldType,
+    /// Whether the field may be `Value::Null`
+    pub nullable: bool,
+}
+
+impl Field {
+    /// Describes a field called `name` holding values of the Rust type `V`
+    pub fn of<V: FieldValue>(name: &str) -> Field {
+        Field { name: name.to_string(), ty: V::TYPE, nullable: V::NULLABLE }
+    }
+
+    /// Returns true if `value` can be stored in this field
+    pub fn accepts(&self, value: &Value) -> bool {
+        match value.field_type() {
+            Some(ty) => ty == self.ty,
+            None => self.nullable,
+        }
+    }
+}
+
+/// The fields of a record, in order
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Schema {
+    fields: Vec<Field>,
+}
+
+impl Schema {
+    /// Makes a schema with the given fields
+    ///
+    /// # Panics
+    ///
+    /// Panics if two fields have the same name.
+    pub fn new(fields: Vec<Field>) -> Schema {
+        for (i, field) in fields.iter().enumerate() {
+            assert!(fields[..i].iter().all(|f| f.name != field.name),
+                    "duplicate field `{}` in schema",
+                    field.name);
+        }
+        Schema { fields }
+    }
+
+    /// Returns the fields, in order
+    pub fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+
+    /// Returns the field called `name`, if there is one
+    pub fn field(&self, name: &str) -> Option<&Field> {
+        self.fields.iter().find(|f| f.name == name)
+    }
+
+    /// Returns the position of the field called `name`, if there is one
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.fields.iter().position(|f| f.name == name)
+    }
+
+    /// Returns the names of the fields, in order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.fields.iter().map(|f| &f.name[..])
+    }
+
+    /// Returns the number of fields
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Returns true if there are no fields
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
+/// A type whose fields can be read by name, which is all the `sql` module needs. Every record
+/// is one, as is `Row`, whose fields differ from row to row.
+pub trait FieldAccess {
+    /// Returns the value of the field called `name`, or `None` if there is no such field
+    fn get(&self, name: &str) -> Option<Value>;
+}
+
+/// A type whose fields can be listed and read by name
+pub trait Record: FieldAccess {
+    /// Returns the fields every value of this type has
+    fn schema() -> Schema;
+
+    /// Makes a record from the value of every field, in the order of the schema
+    fn from_values(values: Vec<Value>) -> Result<Self, TypeError> where Self: Sized;
+
+    /// Returns the value of every field, in the order of the schema
+    fn values(&self) -> Vec<Value> {
+        Self::schema()
+            .names()
+            .map(|name| self.get(name).expect("every field in the schema has a value"))
+            .collect()
+    }
+}
+
+/// A Rust type which can be the type of a record's field
+pub trait FieldValue {
+    const TYPE: FieldType;
+    const NULLABLE: bool = false;
+
+    fn to_value(&self) -> Value;
+
+    /// Converts `value` back, or returns `None` if it has the wrong type or doesn't fit in
+    /// `Self`
+    fn from_value(value: &Value) -> Option<Self> where Self: Sized;
+}
+
+macro_rules! field_value {
+    ($variant:ident: $($t:ty),*) => {
+        $(
+            impl FieldValue for $t {
+                const TYPE: FieldType = FieldType::$variant;
+
+                fn to_value(&self) -> Value {
+                    Value::$variant((*self).into())
+                }
+
+                fn from_value(value: &Value) -> Option<$t> {
+                    match *value {
+                        Value::$variant(x) => TryFrom::try_from(x).ok(),
+                        _ => None,
+                    }
+                }
+            }
+        )*
+    }
+}
+
+field_value!(Bool: bool);
+field_value!(Int: i8, i16, i32, i64, u8, u16, u32);
+
+impl FieldValue for f32 {
+    const TYPE: FieldType = FieldType::Float;
+
+    fn to_value(&self) -> Value {
+        Value::Float((*self).into())
+    }
+
+    fn from_value(value: &Value) -> Option<f32> {
+        match *value {
+            Value::Float(x) => Some(x as f32),
+            _ => None,
+        }
+    }
+}
+
+impl FieldValue for f64 {
+    const TYPE: FieldType = FieldType::Float;
+
+    fn to_value(&self) -> Value {
+        Value::Float(*self)
+    }
+
+    fn from_value(value: &Value) -> Option<f64> {
+        match *value {
+            Value::Float(x) => Some(x),
+            _ => None,
+        }
+    }
+}
+
+impl FieldValue for String {
+    const TYPE: FieldType = FieldType::Str;
+
+    fn to_value(&self) -> Value {
+        Value::Str(self.clone())
+    }
+
+    fn from_value(value: &Value) -> Option<String> {
+        match *value {
+            Value::Str(ref x) => Some(x.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl FieldValue for Vec<u8> {
+    const TYPE: FieldType = FieldType::Bytes;
+
+    fn to_value(&self) -> Value {
+        Value::Bytes(self.clone())
+    }
+
+    fn from_value(value: &Value) -> Option<Vec<u8>> {
+        match *value {
+            Value::Bytes(ref x) => Some(x.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl FieldValue for Vec<Value> {
+    const TYPE: FieldType = FieldType::List;
+
+    fn to_value(&self) -> Value {
+        Value::List(self.clone())
+    }
+
+    fn from_value(value: &Value) -> Option<Vec<Value>> {
+        match *value {
+            Value::List(ref x) => Some(x.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl<V: FieldValue> FieldValue for Option<V> {
+    const TYPE: FieldType = V::TYPE;
+    const NULLABLE: bool = true;
+
+    fn to_value(&self) -> Value {
+        self.as_ref().map_or(Value::Null, FieldValue::to_value)
+    }
+
+    fn from_value(value: &Value) -> Option<Option<V>> {
+        match *value {
+            Value::Null => Some(None),
+            ref value => V::from_value(value).map(Some),
+        }
+    }
+}
+
+/// Defines a struct and implements `Record` and `FieldAccess` for it, with one field in the schema
+/// for each field of the struct. Field types must implement `FieldValue`.
+///
+/// ```
+/// #[macro_use]
+/// extern crate wk3;
+/// use wk3::Record;
+///
+/// record! {
+///     #[derive(Debug)]
+///     pub struct Person {
+///         pub name: String,
+///         pub age: u32,
+///     }
+/// }
+///
+/// fn main() {
+///     let names: Vec<String> = Person::schema().names().map(String::from).collect();
+///     assert_eq!(vec!["name", "age"], names);
+/// }
+/// ```
+#[macro_export]
+macro_rules! record {
+    ($(#[$attr:meta])*
+     $vis:vis struct $name:ident {
+         $($(#[$field_attr:meta])* $field_vis:vis $field:ident : $ty:ty),* $(,)*
+     }) => {
+        $(#[$attr])*
+        $vis struct $name {
+            $($(#[$field_attr])* $field_vis $field: $ty),*
+        }
+
+        impl $crate::FieldAccess for $name {
+            fn get(&self, name: &str) -> Option<$crate::Value> {
+                match name {
+                    $(stringify!($field) => Some($crate::FieldValue::to_value(&self.$field)),)*
+                    _ => None,
+                }
+            }
+        }
+
+        impl $crate::Record for $name {
+            fn schema() -> $crate::Schema {
+                $crate::Schema::new(vec![$($crate::Field::of::<$ty>(stringify!($field))),*])
+            }
+
+            fn from_values(values: Vec<$crate::Value>)
+                -> ::std::result::Result<Self, $crate::TypeError>
+            {
+                <Self as $crate::Record>::schema().check(&values)?;
+                let mut values = values.iter();
+                Ok($name {
+                    $($field: {
+                        let value = values.next().expect("the values follow the schema");
+                        $crate::FieldValue::from_value(value).ok_or_else(|| {
+                            $crate::TypeError::OutOfRange {
+                                field: stringify!($field).to_string(),
+                                value: value.clone(),
+                            }
+                        })?
+                    }),*
+                })
+            }
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! Serde support, behind the `serde` feature
+//!
+//! A DB is serialized as its entries along with their ids, so that ids saved elsewhere still
+//! refer to the same entries once it is loaded again. Indexes and history are not saved: they
+//! hold closures, and have to be set up again on the loaded DB. A DBView is serialized as a plain
+//! list of its entries, which can be loaded back as a `Vec<T>`.
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeSeq, SerializeStruct, Serializer};
+
+use {DB, DBView, RowId};
+
+const FIELDS: &[&str] = &["next_id", "rows"];
+
+impl Serialize for RowId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct("RowId", &self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for RowId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<RowId, D::Error> {
+        deserializer.deserialize_newtype_struct("RowId", RowIdVisitor)
+    }
+}
+
+struct RowIdVisitor;
+
+impl<'de> Visitor<'de> for RowIdVisitor {
+    type Value = RowId;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a row id")
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<RowId, D::Error>
+        where D: Deserializer<'de>
+    {
+        u64::deserialize(deserializer).map(RowId)
+    }
+
+    /// Formats which don't keep newtypes pass the id on as it is
+    fn visit_u64<E: de::Error>(self, id: u64) -> Result<RowId, E> {
+        Ok(RowId(id))
+    }
+}
+
+/// The entries of a DB paired with their ids, in order
+struct Rows<'a, T: 'a>(&'a DB<T>);
+
+impl<'a, T: Serialize> Serialize for Rows<'a, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+        for row in self.0.rows() {
+            seq.serialize_element(&row)?;
+        }
+        seq.end()
+    }
+}
+
+/// Edits still being made through a view can't be seen from `&self`, so serializing a DB that
+/// has handed out entries mutably saves them as they are now.
+impl<T: Serialize> Serialize for DB<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("DB", FIELDS.len())?;
+        state.serialize_field("next_id", &self.next_id)?;
+        state.serialize_field("rows", &Rows(self))?;
+        state.end()
+    }
+}
+
+/// Loading a DB checks that its ids are in increasing order and below `next_id`, so that ids
+/// handed out afterwards can't clash with them.
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for DB<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<DB<T>, D::Error> {
+        deserializer.deserialize_struct("DB", FIELDS, DBVisitor(PhantomData))
+    }
+}
+
+struct DBVisitor<T>(PhantomData<T>);
+
+impl<T> DBVisitor<T> {
+    fn build<E: de::Error>(next_id: u64, rows: Vec<(RowId, T)>) -> Result<DB<T>, E> {
+        let in_order = rows.windows(2).all(|w| w[0].0 < w[1].0);
+        if !in_order {
+            return Err(E::custom("row ids are not in increasing order"));
+        }
+        if rows.last().is_some_and(|&(id, _)| id.0 >= next_id) {
+            return Err(E::custom("a row id is not below `next_id`"));
+        }
+        let (ids, data) = rows.into_iter().unzip();
+        Ok(DB::from_rows(next_id, ids, data))
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for DBVisitor<T> {
+    type Value = DB<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a DB")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<DB<T>, A::Error> {
+        let next_id = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let rows = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        DBVisitor::build(next_id, rows)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<DB<T>, A::Error> {
+        let mut next_id = None;
+        let mut rows = None;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "next_id" if next_id.is_none() => next_id = Some(map.next_value()?),
+                "rows" if rows.is_none() => rows = Some(map.next_value()?),
+                "next_id" | "rows" => return Err(de::Error::custom(format!("duplicate `{}`", key))),
+                _ => return Err(de::Error::unknown_field(&key, FIELDS)),
+            }
+        }
+        let next_id = next_id.ok_or_else(|| de::Error::missing_field("next_id"))?;
+        let rows = rows.ok_or_else(|| de::Error::missing_field("rows"))?;
+        DBVisitor::build(next_id, rows)
+    }
+}
+
+impl<'a, T: Serialize> Serialize for DBView<'a, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for entry in self {
+            seq.serialize_element(entry)?;
+        }
+        seq.end()
+    }
+}
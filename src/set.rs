@@ -0,0 +1,64 @@
+//! Set operations between views
+//!
+//! Entries are compared by identity, not by value: two entries are the same if they are the same
+//! entry of the same DB. This is checked by address along with id, since ids are only unique
+//! within one DB and entries with no size can share an address.
+
+use std::collections::HashSet;
+
+use {DBView, RowId};
+
+/// What identifies an entry across every DB
+type Identity<T> = (RowId, *const T);
+
+impl<'a, T> DBView<'a, T> {
+    /// Creates a new DBView containing every entry that is in `self` or `other`. Entries of `self`
+    /// come first, followed by those only in `other`, each in their original order.
+    ///
+    /// Entries are compared by identity, not by value, so equal entries from different DBs are
+    /// both kept.
+    pub fn union(&self, other: &DBView<'a, T>) -> DBView<'a, T> {
+        let ids = self.id_set();
+        let mut entries = self.entries.clone();
+        entries.extend(other.entries.iter().filter(|&&row| !ids.contains(&identity(row))));
+        DBView { entries }
+    }
+
+    /// Creates a new DBView containing the entries of `self` which are also in `other`, in the
+    /// order of `self`
+    pub fn intersect(&self, other: &DBView<'a, T>) -> DBView<'a, T> {
+        let ids = other.id_set();
+        self.retained(|row| ids.contains(&row))
+    }
+
+    /// Creates a new DBView containing the entries of `self` which are not in `other`, in the
+    /// order of `self`
+    pub fn difference(&self, other: &DBView<'a, T>) -> DBView<'a, T> {
+        let ids = other.id_set();
+        self.retained(|row| !ids.contains(&row))
+    }
+
+    /// Creates a new DBView containing the entries in exactly one of `self` and `other`. Entries
+    /// only in `self` come first.
+    pub fn symmetric_difference(&self, other: &DBView<'a, T>) -> DBView<'a, T> {
+        let mut result = self.difference(other);
+        result.entries.extend(other.difference(self).entries);
+        result
+    }
+
+    fn id_set(&self) -> HashSet<Identity<T>> {
+        self.entries.iter().cloned().map(identity).collect()
+    }
+
+    fn retained<F>(&self, keep: F) -> DBView<'a, T>
+        where F: Fn(Identity<T>) -> bool
+    {
+        DBView {
+            entries: self.entries.iter().cloned().filter(|&row| keep(identity(row))).collect(),
+        }
+    }
+}
+
+fn identity<T>((id, entry): (RowId, &T)) -> Identity<T> {
+    (id, entry)
+}
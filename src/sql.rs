@@ -0,0 +1,687 @@
+//! A small SQL-like language for querying DBs of records with named fields
+//!
+//! A query looks like `SELECT * WHERE age > 30 AND name LIKE 'A%' ORDER BY age DESC LIMIT 5`.
+//! Everything after `SELECT *` is optional, but the clauses that are present must come in the
+//! order `WHERE`, `ORDER BY`, `LIMIT`, `OFFSET`. Keywords are case insensitive.
+//!
+//! A condition compares two operands with `=`, `!=`, `<>`, `<`, `<=`, `>` or `>=`, matches a
+//! string against a `LIKE` pattern (where `%` stands for any run of characters and `_` for any
+//! single one), or checks for `IS NULL`. Operands are field names or literals: integers, floats,
+//! `'strings'` (with `''` for a quote), `TRUE`, `FALSE` and `NULL`. Conditions combine with
+//! `AND`, `OR`, `NOT` and parentheses.
+//!
+//! Integers and floats compare with each other, but values of other differing types never
+//! compare equal, less or greater. Neither does `NULL`, which includes the value of a field the
+//! record doesn't have.
+
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use {DB, DBView, FieldAccess, Predicate, Query, Value};
+
+/// Words which can't be used as field names
+const KEYWORDS: &[&str] = &["SELECT", "WHERE", "AND", "OR", "NOT", "LIKE", "IS", "NULL", "TRUE",
+                            "FALSE", "ORDER", "BY", "ASC", "DESC", "LIMIT", "OFFSET"];
+
+/// A token of a query, along with where it starts
+#[derive(Clone, Debug, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    /// The line the token starts on, counting from 1
+    pub line: usize,
+    /// The column the token starts at, counting characters from 1
+    pub column: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TokenKind {
+    /// A keyword or field name
+    Word(String),
+    Int(i64),
+    Float(f64),
+    /// A string literal, with its quotes removed and escapes replaced
+    Str(String),
+    Star,
+    Comma,
+    Minus,
+    LeftParen,
+    RightParen,
+    Compare(CompareOp),
+    /// The end of the query
+    End,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TokenKind::Word(ref w) => write!(f, "`{}`", w),
+            TokenKind::Int(i) => write!(f, "`{}`", i),
+            TokenKind::Float(x) => write!(f, "`{:?}`", x),
+            TokenKind::Str(ref s) => write!(f, "string {}", Value::Str(s.clone())),
+            TokenKind::Star => write!(f, "`*`"),
+            TokenKind::Comma => write!(f, "`,`"),
+            TokenKind::Minus => write!(f, "`-`"),
+            TokenKind::LeftParen => write!(f, "`(`"),
+            TokenKind::RightParen => write!(f, "`)`"),
+            TokenKind::Compare(op) => write!(f, "`{}`", op),
+            TokenKind::End => write!(f, "end of query"),
+        }
+    }
+}
+
+/// An error in the text of a query, with the position it was found at
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    message: String,
+    line: usize,
+    column: usize,
+}
+
+impl ParseError {
+    fn new(message: String, line: usize, column: usize) -> ParseError {
+        ParseError { message, line, column }
+    }
+
+    /// Returns what went wrong, without the position
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the line of the error, counting from 1
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Returns the column of the error, counting characters from 1
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at line {}, column {}", self.message, self.line, self.column)
+    }
+}
+
+impl Error for ParseError {}
+
+/// Splits `query` into tokens. The last token is always `TokenKind::End`.
+pub fn tokenize(query: &str) -> Result<Vec<Token>, ParseError> {
+    let mut lexer = Lexer { chars: query.chars().peekable(), line: 1, column: 1 };
+    let mut tokens = Vec::new();
+    loop {
+        while lexer.chars.peek().is_some_and(|c| c.is_whitespace()) {
+            lexer.bump();
+        }
+        let (line, column) = (lexer.line, lexer.column);
+        let c = match lexer.chars.peek() {
+            Some(&c) => c,
+            None => {
+                tokens.push(Token { kind: TokenKind::End, line, column });
+                return Ok(tokens);
+            }
+        };
+        let kind = match c {
+            '\'' => lexer.string()?,
+            c if c.is_ascii_digit() => lexer.number()?,
+            c if c.is_alphabetic() || c == '_' => {
+                let mut word = String::new();
+                while let Some(c) = lexer.bump_if(|c| c.is_alphanumeric() || c == '_') {
+                    word.push(c);
+                }
+                TokenKind::Word(word)
+            }
+            _ => {
+                lexer.bump();
+                match c {
+                    '*' => TokenKind::Star,
+                    ',' => TokenKind::Comma,
+                    '-' => TokenKind::Minus,
+                    '(' => TokenKind::LeftParen,
+                    ')' => TokenKind::RightParen,
+                    '=' => TokenKind::Compare(CompareOp::Eq),
+                    '!' if lexer.bump_if(|c| c == '=').is_some() => {
+                        TokenKind::Compare(CompareOp::NotEq)
+                    }
+                    '<' if lexer.bump_if(|c| c == '=').is_some() => {
+                        TokenKind::Compare(CompareOp::LtEq)
+                    }
+                    '<' if lexer.bump_if(|c| c == '>').is_some() => {
+                        TokenKind::Compare(CompareOp::NotEq)
+                    }
+                    '<' => TokenKind::Compare(CompareOp::Lt),
+                    '>' if lexer.bump_if(|c| c == '=').is_some() => {
+                        TokenKind::Compare(CompareOp::GtEq)
+                    }
+                    '>' => TokenKind::Compare(CompareOp::Gt),
+                    _ => {
+                        let message = format!("unexpected character `{}`", c);
+                        return Err(ParseError::new(message, line, column));
+                    }
+                }
+            }
+        };
+        tokens.push(Token { kind, line, column });
+    }
+}
+
+struct Lexer<'q> {
+    chars: Peekable<Chars<'q>>,
+    line: usize,
+    column: usize,
+}
+
+impl<'q> Lexer<'q> {
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn bump_if<F: Fn(char) -> bool>(&mut self, accept: F) -> Option<char> {
+        match self.chars.peek() {
+            Some(&c) if accept(c) => self.bump(),
+            _ => None,
+        }
+    }
+
+    fn error(&self, message: &str) -> ParseError {
+        ParseError::new(message.to_string(), self.line, self.column)
+    }
+
+    fn string(&mut self) -> Result<TokenKind, ParseError> {
+        let start = self.error("unterminated string");
+        self.bump();
+        let mut s = String::new();
+        loop {
+            match self.bump() {
+                Some('\'') if self.bump_if(|c| c == '\'').is_some() => s.push('\''),
+                Some('\'') => return Ok(TokenKind::Str(s)),
+                Some(c) => s.push(c),
+                None => return Err(start),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<TokenKind, ParseError> {
+        let start = self.error("integer is too large");
+        let mut digits = String::new();
+        while let Some(c) = self.bump_if(|c| c.is_ascii_digit()) {
+            digits.push(c);
+        }
+        if self.bump_if(|c| c == '.').is_none() {
+            return digits.parse().map(TokenKind::Int).map_err(|_| start);
+        }
+        digits.push('.');
+        match self.bump_if(|c| c.is_ascii_digit()) {
+            Some(c) => digits.push(c),
+            None => return Err(self.error("expected a digit after the decimal point")),
+        }
+        while let Some(c) = self.bump_if(|c| c.is_ascii_digit()) {
+            digits.push(c);
+        }
+        Ok(TokenKind::Float(digits.parse().expect("digits with one decimal point")))
+    }
+}
+
+/// A parsed query. Made by `parse`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Select {
+    /// The condition of the `WHERE` clause, if there is one
+    pub filter: Option<Expr>,
+    /// The keys of the `ORDER BY` clause, most significant first
+    pub order_by: Vec<OrderKey>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+/// A key of an `ORDER BY` clause
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OrderKey {
+    pub field: String,
+    pub descending: bool,
+}
+
+/// A condition of a `WHERE` clause. Conditions are predicates on any type with `FieldAccess`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Operand, CompareOp, Operand),
+    /// `operand LIKE pattern`, or `operand NOT LIKE pattern` if `negated`
+    Like {
+        operand: Operand,
+        pattern: String,
+        negated: bool,
+    },
+    /// `operand IS NULL`, or `operand IS NOT NULL` if `negated`
+    IsNull { operand: Operand, negated: bool },
+}
+
+/// Either side of a comparison
+#[derive(Clone, Debug, PartialEq)]
+pub enum Operand {
+    Field(String),
+    Literal(Value),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+}
+
+impl fmt::Display for CompareOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            CompareOp::Eq => "=",
+            CompareOp::NotEq => "!=",
+            CompareOp::Lt => "<",
+            CompareOp::LtEq => "<=",
+            CompareOp::Gt => ">",
+            CompareOp::GtEq => ">=",
+        })
+    }
+}
+
+/// Parses `query` into a `Select`, which can then be run against any number of DBs
+pub fn parse(query: &str) -> Result<Select, ParseError> {
+    let mut parser = Parser { tokens: tokenize(query)?, pos: 0 };
+    parser.select()
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &TokenKind {
+        &self.tokens[self.pos].kind
+    }
+
+    /// Moves past the current token, unless it is the end
+    fn advance(&mut self) -> TokenKind {
+        let kind = self.tokens[self.pos].kind.clone();
+        if self.pos + 1 < self.tokens.len() {
+            self.pos += 1;
+        }
+        kind
+    }
+
+    /// Returns an error saying that `expected` was expected at the current token
+    fn expected(&self, expected: &str) -> ParseError {
+        let token = &self.tokens[self.pos];
+        let message = format!("expected {}, found {}", expected, token.kind);
+        ParseError::new(message, token.line, token.column)
+    }
+
+    fn eat(&mut self, kind: &TokenKind) -> bool {
+        if self.peek() == kind {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, kind: TokenKind) -> Result<(), ParseError> {
+        if self.eat(&kind) {
+            Ok(())
+        } else {
+            Err(self.expected(&kind.to_string()))
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let found = match *self.peek() {
+            TokenKind::Word(ref w) => w.eq_ignore_ascii_case(keyword),
+            _ => false,
+        };
+        if found {
+            self.advance();
+        }
+        found
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), ParseError> {
+        if self.keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.expected(&format!("`{}`", keyword)))
+        }
+    }
+
+    fn select(&mut self) -> Result<Select, ParseError> {
+        self.expect_keyword("SELECT")?;
+        self.expect(TokenKind::Star)?;
+        let filter = if self.keyword("WHERE") { Some(self.or()?) } else { None };
+        let mut order_by = Vec::new();
+        if self.keyword("ORDER") {
+            self.expect_keyword("BY")?;
+            loop {
+                let field = self.field()?;
+                let descending = self.keyword("DESC");
+                if !descending {
+                    self.keyword("ASC");
+                }
+                order_by.push(OrderKey { field, descending });
+                if !self.eat(&TokenKind::Comma) {
+                    break;
+                }
+            }
+        }
+        let limit = if self.keyword("LIMIT") { Some(self.count()?) } else { None };
+        let offset = if self.keyword("OFFSET") { Some(self.count()?) } else { None };
+        if *self.peek() != TokenKind::End {
+            return Err(self.expected("end of query"));
+        }
+        Ok(Select { filter, order_by, limit, offset })
+    }
+
+    fn field(&mut self) -> Result<String, ParseError> {
+        match *self.peek() {
+            TokenKind::Word(ref w) if !is_keyword(w) => {}
+            _ => return Err(self.expected("a field name")),
+        }
+        match self.advance() {
+            TokenKind::Word(w) => Ok(w),
+            _ => unreachable!("just peeked a word"),
+        }
+    }
+
+    fn count(&mut self) -> Result<usize, ParseError> {
+        match *self.peek() {
+            TokenKind::Int(i) if i >= 0 => {
+                self.advance();
+                Ok(i as usize)
+            }
+            _ => Err(self.expected("a non-negative integer")),
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.and()?;
+        while self.keyword("OR") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.not()?;
+        while self.keyword("AND") {
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> Result<Expr, ParseError> {
+        if self.keyword("NOT") {
+            Ok(Expr::Not(Box::new(self.not()?)))
+        } else {
+            self.condition()
+        }
+    }
+
+    fn condition(&mut self) -> Result<Expr, ParseError> {
+        if self.eat(&TokenKind::LeftParen) {
+            let expr = self.or()?;
+            self.expect(TokenKind::RightParen)?;
+            return Ok(expr);
+        }
+        let operand = self.operand()?;
+        if self.keyword("IS") {
+            let negated = self.keyword("NOT");
+            self.expect_keyword("NULL")?;
+            return Ok(Expr::IsNull { operand, negated });
+        }
+        let negated = self.keyword("NOT");
+        if negated || self.keyword("LIKE") {
+            if negated {
+                self.expect_keyword("LIKE")?;
+            }
+            let pattern = match *self.peek() {
+                TokenKind::Str(ref s) => s.clone(),
+                _ => return Err(self.expected("a string pattern")),
+            };
+            self.advance();
+            return Ok(Expr::Like { operand, pattern, negated });
+        }
+        let op = match *self.peek() {
+            TokenKind::Compare(op) => op,
+            _ => return Err(self.expected("a comparison, `LIKE` or `IS`")),
+        };
+        self.advance();
+        Ok(Expr::Compare(operand, op, self.operand()?))
+    }
+
+    fn operand(&mut self) -> Result<Operand, ParseError> {
+        let negative = self.eat(&TokenKind::Minus);
+        let value = match *self.peek() {
+            TokenKind::Int(i) => Value::Int(if negative { -i } else { i }),
+            TokenKind::Float(x) => Value::Float(if negative { -x } else { x }),
+            _ if negative => return Err(self.expected("a number")),
+            TokenKind::Str(ref s) => Value::Str(s.clone()),
+            TokenKind::Word(ref w) if w.eq_ignore_ascii_case("TRUE") => Value::Bool(true),
+            TokenKind::Word(ref w) if w.eq_ignore_ascii_case("FALSE") => Value::Bool(false),
+            TokenKind::Word(ref w) if w.eq_ignore_ascii_case("NULL") => Value::Null,
+            TokenKind::Word(ref w) if !is_keyword(w) => return self.field().map(Operand::Field),
+            _ => return Err(self.expected("a field name or value")),
+        };
+        self.advance();
+        Ok(Operand::Literal(value))
+    }
+}
+
+fn is_keyword(word: &str) -> bool {
+    KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(word))
+}
+
+impl Operand {
+    fn eval<T: FieldAccess>(&self, entry: &T) -> Cow<'_, Value> {
+        match *self {
+            Operand::Field(ref name) => Cow::Owned(entry.get(name).unwrap_or(Value::Null)),
+            Operand::Literal(ref value) => Cow::Borrowed(value),
+        }
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Operand::Field(ref name) => f.write_str(name),
+            Operand::Literal(ref value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// Compares two values the way a query does, returning `None` if they don't compare at all
+fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        (Value::Int(a), Value::Int(b)) => Some(a.cmp(b)),
+        (Value::Int(a), Value::Float(b)) => (*a as f64).partial_cmp(b),
+        (Value::Float(a), Value::Int(b)) => a.partial_cmp(&(*b as f64)),
+        (Value::Float(a), Value::Float(b)) => a.partial_cmp(b),
+        (Value::Str(a), Value::Str(b)) => Some(a.cmp(b)),
+        (Value::Bytes(a), Value::Bytes(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+/// Returns true if all of `s` matches the `LIKE` pattern `pattern`
+fn like(s: &str, pattern: &str) -> bool {
+    let s: Vec<char> = s.chars().collect();
+    let pattern: Vec<char> = pattern.chars().collect();
+    let (mut i, mut p) = (0, 0);
+    // Where to resume if the text after the last `%` fails to match: the pattern position just
+    // past the `%`, and the position in `s` it was last tried at
+    let mut backtrack = None;
+    while i < s.len() {
+        match pattern.get(p) {
+            Some(&'%') => {
+                p += 1;
+                backtrack = Some((p, i));
+            }
+            Some(&c) if c == '_' || c == s[i] => {
+                i += 1;
+                p += 1;
+            }
+            _ => {
+                match backtrack {
+                    Some((after, tried)) => {
+                        p = after;
+                        i = tried + 1;
+                        backtrack = Some((after, i));
+                    }
+                    None => return false,
+                }
+            }
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '%')
+}
+
+impl<T: FieldAccess> Predicate<T> for Expr {
+    fn test(&self, entry: &T) -> bool {
+        match *self {
+            Expr::And(ref a, ref b) => a.test(entry) && b.test(entry),
+            Expr::Or(ref a, ref b) => a.test(entry) || b.test(entry),
+            Expr::Not(ref a) => !Predicate::<T>::test(&**a, entry),
+            Expr::Compare(ref a, op, ref b) => {
+                let ordering = match compare(&a.eval(entry), &b.eval(entry)) {
+                    Some(ordering) => ordering,
+                    None => return false,
+                };
+                match op {
+                    CompareOp::Eq => ordering == Ordering::Equal,
+                    CompareOp::NotEq => ordering != Ordering::Equal,
+                    CompareOp::Lt => ordering == Ordering::Less,
+                    CompareOp::LtEq => ordering != Ordering::Greater,
+                    CompareOp::Gt => ordering == Ordering::Greater,
+                    CompareOp::GtEq => ordering != Ordering::Less,
+                }
+            }
+            Expr::Like { ref operand, ref pattern, negated } => {
+                match *operand.eval(entry) {
+                    Value::Str(ref s) => like(s, pattern) != negated,
+                    _ => false,
+                }
+            }
+            Expr::IsNull { ref operand, negated } => {
+                (*operand.eval(entry) == Value::Null) != negated
+            }
+        }
+    }
+
+    fn describe(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+/// Prints the condition as it would be written in a query, with parentheses around every `AND`
+/// and `OR`
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Expr::And(ref a, ref b) => write!(f, "({} AND {})", a, b),
+            Expr::Or(ref a, ref b) => write!(f, "({} OR {})", a, b),
+            Expr::Not(ref a) => write!(f, "NOT {}", a),
+            Expr::Compare(ref a, op, ref b) => write!(f, "{} {} {}", a, op, b),
+            Expr::Like { ref operand, ref pattern, negated } => {
+                let not = if negated { "NOT " } else { "" };
+                write!(f, "{} {}LIKE {}", operand, not, Value::Str(pattern.clone()))
+            }
+            Expr::IsNull { ref operand, negated } => {
+                write!(f, "{} IS {}NULL", operand, if negated { "NOT " } else { "" })
+            }
+        }
+    }
+}
+
+/// A value with a total order, so fields can be sorted on: `NULL` first, then booleans, numbers,
+/// strings, bytes and lists
+#[derive(PartialEq)]
+struct SortValue(Value);
+
+impl Eq for SortValue {}
+
+impl PartialOrd for SortValue {
+    fn partial_cmp(&self, other: &SortValue) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SortValue {
+    fn cmp(&self, other: &SortValue) -> Ordering {
+        total_cmp(&self.0, &other.0)
+    }
+}
+
+fn total_cmp(a: &Value, b: &Value) -> Ordering {
+    fn rank(value: &Value) -> u8 {
+        match *value {
+            Value::Null => 0,
+            Value::Bool(_) => 1,
+            Value::Int(_) | Value::Float(_) => 2,
+            Value::Str(_) => 3,
+            Value::Bytes(_) => 4,
+            Value::List(_) => 5,
+        }
+    }
+    match (a, b) {
+        (Value::Int(a), Value::Float(b)) => (*a as f64).total_cmp(b),
+        (Value::Float(a), Value::Int(b)) => a.total_cmp(&(*b as f64)),
+        (Value::Float(a), Value::Float(b)) => a.total_cmp(b),
+        (Value::List(a), Value::List(b)) => {
+            let unequal = a.iter().zip(b).map(|(a, b)| total_cmp(a, b)).find(|o| o.is_ne());
+            unequal.unwrap_or_else(|| a.len().cmp(&b.len()))
+        }
+        (a, b) => compare(a, b).unwrap_or_else(|| rank(a).cmp(&rank(b))),
+    }
+}
+
+impl Select {
+    /// Builds a query against `db` which does what `self` says
+    pub fn query<'a, T: FieldAccess>(&self, db: &'a DB<T>) -> Query<'a, T> {
+        let mut query = db.query();
+        if let Some(ref filter) = self.filter {
+            query = query.filter_by(filter.clone());
+        }
+        for key in &self.order_by {
+            let field = key.field.clone();
+            let value = move |entry: &T| SortValue(entry.get(&field).unwrap_or(Value::Null));
+            query = if key.descending { query.order_by_desc(value) } else { query.order_by(value) };
+        }
+        if let Some(offset) = self.offset {
+            query = query.offset(offset);
+        }
+        if let Some(limit) = self.limit {
+            query = query.limit(limit);
+        }
+        query
+    }
+}
+
+impl<T: FieldAccess> DB<T> {
+    /// Parses and runs `query`
+    pub fn sql(&self, query: &str) -> Result<DBView<'_, T>, ParseError> {
+        Ok(parse(query)?.query(self).collect_view())
+    }
+}
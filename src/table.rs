@@ -0,0 +1,330 @@
+//! Untyped rows and tables, for data whose shape is only known at run time
+//!
+//! A `Table` is a `DB<Row>` paired with the `Schema` every row must follow. It derefs to the DB,
+//! so the usual views, queries and aggregates all work on it. Anything that could add a row or
+//! change a value goes through the table instead, which checks types. Rows are only lent out
+//! mutably as `RowMut`s, which can change values with `set` but can't replace the whole row.
+
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
+use std::mem;
+use std::ops::Deref;
+use std::sync::Arc;
+
+use {DB, DBViewMut, FieldAccess, FieldType, IterMut, RowId, Schema, Value, ViewMutIntoIter,
+     ViewMutIter, ViewMutIterMut};
+
+/// A row of values following a schema
+#[derive(Clone, Debug, PartialEq)]
+pub struct Row {
+    schema: Arc<Schema>,
+    values: Vec<Value>,
+}
+
+/// A value that doesn't fit a schema
+#[derive(Clone, Debug, PartialEq)]
+pub enum TypeError {
+    /// A row had `found` values, rather than one for each of the `expected` fields
+    WrongLength { expected: usize, found: usize },
+    /// The field called `field` holds values of type `expected`, but was given one of type
+    /// `found`, or `NULL` if `found` is `None`
+    WrongType {
+        field: String,
+        expected: FieldType,
+        found: Option<FieldType>,
+    },
+    /// There is no field with this name
+    UnknownField(String),
+    /// `value` has the right type for the field called `field`, but is too big or small for the
+    /// Rust type the field is stored as
+    OutOfRange { field: String, value: Value },
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TypeError::WrongLength { expected, found } => {
+                write!(f, "expected {} values, found {}", expected, found)
+            }
+            TypeError::WrongType { ref field, expected, found: Some(found) } => {
+                write!(f, "field `{}` holds {:?} values, found {:?}", field, expected, found)
+            }
+            TypeError::WrongType { ref field, found: None, .. } => {
+                write!(f, "field `{}` is not nullable, found NULL", field)
+            }
+            TypeError::UnknownField(ref field) => write!(f, "no field called `{}`", field),
+            TypeError::OutOfRange { ref field, ref value } => {
+                write!(f, "{} is out of range for field `{}`", value, field)
+            }
+        }
+    }
+}
+
+impl Error for TypeError {}
+
+impl Schema {
+    /// Checks that `values` has one value for each field, each of which fits its field
+    pub fn check(&self, values: &[Value]) -> Result<(), TypeError> {
+        if values.len() != self.len() {
+            return Err(TypeError::WrongLength { expected: self.len(), found: values.len() });
+        }
+        for (field, value) in self.fields().iter().zip(values) {
+            if !field.accepts(value) {
+                return Err(TypeError::WrongType {
+                    field: field.name.clone(),
+                    expected: field.ty,
+                    found: value.field_type(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Row {
+    /// Makes a row of `values`, which must follow `schema`
+    pub fn new(schema: Arc<Schema>, values: Vec<Value>) -> Result<Row, TypeError> {
+        schema.check(&values)?;
+        Ok(Row { schema, values })
+    }
+
+    /// Returns the schema the row follows
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    /// Returns the value of the field called `name`, or `None` if there is no such field
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.schema.position(name).map(|i| &self.values[i])
+    }
+
+    /// Returns the values of the row, in the order of its schema
+    pub fn values(&self) -> &[Value] {
+        &self.values
+    }
+
+    /// Replaces the value of the field called `name`, returning the old value. Nothing changes
+    /// if `value` doesn't fit the field.
+    pub fn set<V: Into<Value>>(&mut self, name: &str, value: V) -> Result<Value, TypeError> {
+        let unknown = || TypeError::UnknownField(name.to_string());
+        let i = self.schema.position(name).ok_or_else(unknown)?;
+        let value = value.into();
+        let field = &self.schema.fields()[i];
+        if !field.accepts(&value) {
+            return Err(TypeError::WrongType {
+                field: field.name.clone(),
+                expected: field.ty,
+                found: value.field_type(),
+            });
+        }
+        Ok(mem::replace(&mut self.values[i], value))
+    }
+
+    /// Returns the values of the row, in schema order
+    pub fn into_values(self) -> Vec<Value> {
+        self.values
+    }
+}
+
+/// A row of a table borrowed mutably. Its values can only be changed with `set`, so it keeps
+/// following the table's schema.
+#[derive(Debug)]
+pub struct RowMut<'a> {
+    row: &'a mut Row,
+}
+
+impl<'a> RowMut<'a> {
+    /// Like `Row::set`
+    pub fn set<V: Into<Value>>(&mut self, name: &str, value: V) -> Result<Value, TypeError> {
+        self.row.set(name, value)
+    }
+}
+
+impl<'a> Deref for RowMut<'a> {
+    type Target = Row;
+
+    fn deref(&self) -> &Row {
+        self.row
+    }
+}
+
+/// Turns an iterator over `&mut Row`s into one over `RowMut`s
+#[derive(Debug)]
+pub struct RowMutIter<I> {
+    inner: I,
+}
+
+impl<'a, I: Iterator<Item = &'a mut Row>> Iterator for RowMutIter<I> {
+    type Item = RowMut<'a>;
+
+    fn next(&mut self) -> Option<RowMut<'a>> {
+        self.inner.next().map(|row| RowMut { row })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, I> DoubleEndedIterator for RowMutIter<I>
+    where I: DoubleEndedIterator<Item = &'a mut Row>
+{
+    fn next_back(&mut self) -> Option<RowMut<'a>> {
+        self.inner.next_back().map(|row| RowMut { row })
+    }
+}
+
+impl<'a, I: ExactSizeIterator<Item = &'a mut Row>> ExactSizeIterator for RowMutIter<I> {}
+
+/// A mutably borrowed subset of a table, whose rows are lent out as `RowMut`s
+#[derive(Debug)]
+pub struct TableViewMut<'a> {
+    view: DBViewMut<'a, Row>,
+}
+
+impl<'a> TableViewMut<'a> {
+    /// Like `DBViewMut::select_where_mut`
+    pub fn select_where_mut<F: Fn(&Row) -> bool>(self, predicate: F) -> TableViewMut<'a> {
+        TableViewMut { view: self.view.select_where_mut(predicate) }
+    }
+
+    /// Returns the number of rows in `self`
+    pub fn len(&self) -> usize {
+        self.view.len()
+    }
+
+    /// Returns true if `self` has no rows
+    pub fn is_empty(&self) -> bool {
+        self.view.is_empty()
+    }
+
+    /// Returns an iterator over shared references to the rows of `self`
+    pub fn iter<'v>(&'v self) -> ViewMutIter<'v, 'a, Row> {
+        self.view.iter()
+    }
+
+    /// Returns an iterator over the rows of `self`, borrowed mutably
+    pub fn iter_mut<'v>(&'v mut self) -> RowMutIter<ViewMutIterMut<'v, 'a, Row>> {
+        RowMutIter { inner: self.view.iter_mut() }
+    }
+}
+
+impl<'a> IntoIterator for TableViewMut<'a> {
+    type Item = RowMut<'a>;
+    type IntoIter = RowMutIter<ViewMutIntoIter<'a, Row>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        RowMutIter { inner: self.view.into_iter() }
+    }
+}
+
+impl<'v, 'a> IntoIterator for &'v mut TableViewMut<'a> {
+    type Item = RowMut<'v>;
+    type IntoIter = RowMutIter<ViewMutIterMut<'v, 'a, Row>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl FieldAccess for Row {
+    fn get(&self, name: &str) -> Option<Value> {
+        Row::get(self, name).cloned()
+    }
+}
+
+/// A DB of rows which all follow one schema
+#[derive(Debug, PartialEq)]
+pub struct Table {
+    schema: Arc<Schema>,
+    db: DB<Row>,
+}
+
+impl Table {
+    /// Makes an empty table whose rows follow `schema`
+    pub fn new(schema: Schema) -> Table {
+        Table { schema: Arc::new(schema), db: DB::new(Vec::new()) }
+    }
+
+    /// Returns the schema every row of the table follows
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    /// Adds a row of `values` to the end of the table, returning the id it was given, if the
+    /// values follow the table's schema
+    pub fn insert(&mut self, values: Vec<Value>) -> Result<RowId, TypeError> {
+        let row = Row::new(self.schema.clone(), values)?;
+        Ok(self.db.insert(row))
+    }
+
+    /// Like `DB::remove`
+    pub fn remove(&mut self, id: RowId) -> Option<Row> {
+        self.db.remove(id)
+    }
+
+    /// Like `DB::remove_where`
+    pub fn remove_where<F: Fn(&Row) -> bool>(&mut self, predicate: F) -> Vec<Row> {
+        self.db.remove_where(predicate)
+    }
+
+    /// Like `DB::retain`
+    pub fn retain<F: Fn(&Row) -> bool>(&mut self, predicate: F) {
+        self.db.retain(predicate)
+    }
+
+    /// Like `DB::select_where_mut`
+    pub fn select_where_mut<F: Fn(&Row) -> bool>(&mut self, predicate: F) -> TableViewMut<'_> {
+        TableViewMut { view: self.db.select_where_mut(predicate) }
+    }
+
+    /// Like `DB::as_view_mut`
+    pub fn as_view_mut(&mut self) -> TableViewMut<'_> {
+        TableViewMut { view: self.db.as_view_mut() }
+    }
+
+    /// Like `DB::get_mut`
+    pub fn get_mut(&mut self, id: RowId) -> Option<RowMut<'_>> {
+        self.db.get_mut(id).map(|row| RowMut { row })
+    }
+
+    /// Like `DB::iter_mut`
+    pub fn iter_mut(&mut self) -> RowMutIter<IterMut<'_, Row>> {
+        RowMutIter { inner: self.db.iter_mut() }
+    }
+
+    /// Like `DB::create_index`
+    pub fn create_index<K, F>(&mut self, name: &str, key: F)
+        where K: Hash + Eq + Clone + 'static,
+              F: Fn(&Row) -> K + 'static
+    {
+        self.db.create_index(name, key)
+    }
+
+    /// Like `DB::create_ordered_index`
+    pub fn create_ordered_index<K, F>(&mut self, name: &str, key: F)
+        where K: Ord + Clone + 'static,
+              F: Fn(&Row) -> K + 'static
+    {
+        self.db.create_ordered_index(name, key)
+    }
+
+    /// Like `DB::drop_index`
+    pub fn drop_index(&mut self, name: &str) -> bool {
+        self.db.drop_index(name)
+    }
+
+    /// Returns the DB of rows underneath the table
+    pub fn into_db(self) -> DB<Row> {
+        self.db
+    }
+}
+
+impl Deref for Table {
+    type Target = DB<Row>;
+
+    fn deref(&self) -> &DB<Row> {
+        &self.db
+    }
+}
@@ -0,0 +1,189 @@
+//! Transactions: groups of changes to a DB which take effect together or not at all
+//!
+//! Changes made in a transaction are applied to the DB straight away, so the transaction sees its
+//! own writes, but each one is logged along with how to undo it. Nothing else can see the DB
+//! until the transaction ends, since it borrows the DB mutably, so to everything outside the
+//! changes happen at once when it commits. If it fails instead, the log is played backwards. A
+//! durable DB appends a transaction's changes to its write-ahead log together, when it commits.
+
+use std::ops::Deref;
+
+use {DB, DBViewMut, IterMut, RowId};
+
+/// A transaction in progress, through which its changes are made. Made by `DB::transaction`. It
+/// derefs to the DB, which can be read through it as normal.
+pub struct Transaction<'db, T: 'db> {
+    db: &'db mut DB<T>,
+    undo: Vec<Undo<T>>,
+    committed: bool,
+}
+
+/// How to undo one change
+enum Undo<T> {
+    /// Remove the entry which was inserted with this id
+    Insert(RowId),
+    /// Put back this entry, which was removed
+    Remove(RowId, T),
+    /// Put back this copy of an entry, which may have been changed in place
+    Edit(RowId, T),
+}
+
+impl<T: Clone> DB<T> {
+    /// Runs `f` as a transaction. If it returns `Ok`, its changes are kept. If it returns `Err`
+    /// or panics, every change it made is undone and the DB is left as it was, except that ids
+    /// handed out to entries inserted during the transaction won't be reused.
+    ///
+    /// Entries handed out mutably by the transaction are copied first, so they can be restored.
+    pub fn transaction<R, E, F>(&mut self, f: F) -> Result<R, E>
+        where F: FnOnce(&mut Transaction<'_, T>) -> Result<R, E>
+    {
+        self.sync_edits();
+        if let Some(ref mut history) = self.history {
+            history.begin_group();
+        }
+        if let Some(ref mut wal) = self.wal {
+            wal.begin();
+        }
+        let mut tx = Transaction { db: self, undo: Vec::new(), committed: false };
+        let result = f(&mut tx);
+        if result.is_ok() {
+            tx.commit();
+        }
+        result
+    }
+}
+
+impl<'db, T: Clone> Transaction<'db, T> {
+    /// Runs `f` within a savepoint. If it returns `Err`, the changes it made are undone but the
+    /// rest of the transaction carries on. If it returns `Ok`, its changes become part of the
+    /// transaction, and are still undone if the transaction fails. Savepoints can be nested.
+    pub fn savepoint<R, E, F>(&mut self, f: F) -> Result<R, E>
+        where F: FnOnce(&mut Transaction<'db, T>) -> Result<R, E>
+    {
+        let mark = self.undo.len();
+        let result = f(self);
+        if result.is_err() {
+            self.rollback_to(mark);
+        }
+        result
+    }
+
+    /// Like `DB::insert`
+    pub fn insert(&mut self, entry: T) -> RowId {
+        let id = self.db.insert(entry);
+        self.undo.push(Undo::Insert(id));
+        id
+    }
+
+    /// Like `DB::remove`
+    pub fn remove(&mut self, id: RowId) -> Option<T> {
+        let entry = self.db.remove(id)?;
+        self.undo.push(Undo::Remove(id, entry.clone()));
+        Some(entry)
+    }
+
+    /// Like `DB::remove_where`
+    pub fn remove_where<F>(&mut self, predicate: F) -> Vec<T>
+        where F: Fn(&T) -> bool
+    {
+        let removed = self.db.remove_rows_where(predicate);
+        self.undo.extend(removed.iter().map(|&(id, ref t)| Undo::Remove(id, t.clone())));
+        removed.into_iter().map(|(_, t)| t).collect()
+    }
+
+    /// Like `DB::retain`
+    pub fn retain<F>(&mut self, predicate: F)
+        where F: Fn(&T) -> bool
+    {
+        self.remove_where(|t| !predicate(t));
+    }
+
+    /// Like `DB::get_mut`
+    pub fn get_mut(&mut self, id: RowId) -> Option<&mut T> {
+        let entry = self.db.get_mut(id)?;
+        self.undo.push(Undo::Edit(id, entry.clone()));
+        Some(entry)
+    }
+
+    /// Like `DB::select_where_mut`
+    pub fn select_where_mut<F>(&mut self, predicate: F) -> DBViewMut<'_, T>
+        where F: Fn(&T) -> bool
+    {
+        let view = self.db.select_where_mut(predicate);
+        self.undo.extend(view.entries.iter().map(|&(id, ref t)| Undo::Edit(id, (**t).clone())));
+        view
+    }
+
+    /// Like `DB::as_view_mut`
+    pub fn as_view_mut(&mut self) -> DBViewMut<'_, T> {
+        self.select_where_mut(|_| true)
+    }
+
+    /// Like `DB::iter_mut`
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        self.undo.extend(self.db.rows().map(|(id, t)| Undo::Edit(id, t.clone())));
+        self.db.iter_mut()
+    }
+}
+
+impl<'db, T> Transaction<'db, T> {
+    fn commit(&mut self) {
+        self.db.sync_edits();
+        self.undo.clear();
+        if let Some(ref mut history) = self.db.history {
+            history.end_group();
+        }
+        if let Some(ref mut wal) = self.db.wal {
+            wal.commit();
+        }
+        self.committed = true;
+    }
+
+    /// Undoes changes, newest first, until only the first `mark` are left
+    fn rollback_to(&mut self, mark: usize) {
+        for undo in self.undo.drain(mark..).rev() {
+            match undo {
+                Undo::Insert(id) => {
+                    self.db.remove(id);
+                }
+                Undo::Remove(id, entry) => self.db.restore(id, entry),
+                Undo::Edit(id, entry) => {
+                    if let Some(t) = self.db.get_mut(id) {
+                        *t = entry;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A transaction which is dropped without committing, either because it failed or because it
+/// panicked, is rolled back
+impl<'db, T> Drop for Transaction<'db, T> {
+    fn drop(&mut self) {
+        if self.committed {
+            return;
+        }
+        // Undoing the changes shouldn't itself be recorded in the DB's history, or logged, as the
+        // changes being undone never were. Entries put back in place are synced straight away, so
+        // they don't show up as edits later.
+        let history = self.db.history.take();
+        self.rollback_to(0);
+        self.db.sync_edits();
+        self.db.history = history;
+        if let Some(ref mut history) = self.db.history {
+            history.discard_group();
+        }
+        if let Some(ref mut wal) = self.db.wal {
+            wal.abort();
+        }
+    }
+}
+
+impl<'db, T> Deref for Transaction<'db, T> {
+    type Target = DB<T>;
+
+    fn deref(&self) -> &DB<T> {
+        self.db
+    }
+}
@@ -0,0 +1,134 @@
+//! Bulk updates through a DBViewMut, reporting what they changed
+
+use {DBViewMut, RowId};
+
+/// What an update did. Made by `DBViewMut::update` and friends.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UpdateReport<T> {
+    affected: Vec<RowId>,
+    modified: Option<Vec<RowId>>,
+    diff: Option<Vec<Change<T>>>,
+}
+
+/// An entry before and after it was modified by an update
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Change<T> {
+    pub id: RowId,
+    pub before: T,
+    pub after: T,
+}
+
+impl<T> UpdateReport<T> {
+    /// Returns the ids of every entry the update was applied to, in the order of the view
+    pub fn affected(&self) -> &[RowId] {
+        &self.affected
+    }
+
+    /// Returns the ids of the entries which compare unequal to how they were before the update,
+    /// if the update looked for them
+    pub fn modified(&self) -> Option<&[RowId]> {
+        self.modified.as_deref()
+    }
+
+    /// Returns how many entries were modified, if the update looked for them
+    pub fn modified_count(&self) -> Option<usize> {
+        self.modified.as_ref().map(Vec::len)
+    }
+
+    /// Returns each modified entry before and after the update, if a diff was asked for
+    pub fn diff(&self) -> Option<&[Change<T>]> {
+        self.diff.as_deref()
+    }
+
+    /// Returns each modified entry before and after the update, if a diff was asked for,
+    /// consuming the report
+    pub fn into_diff(self) -> Option<Vec<Change<T>>> {
+        self.diff
+    }
+}
+
+impl<'a, T> DBViewMut<'a, T> {
+    /// Applies `f` to every entry of `self`. The report only says which entries were affected;
+    /// `update_checked` also finds which were really modified.
+    pub fn update<F>(&mut self, f: F) -> UpdateReport<T>
+        where F: FnMut(&mut T)
+    {
+        self.update_where(|_| true, f)
+    }
+
+    /// Applies `f` to every entry of `self` which satisfies `predicate`
+    pub fn update_where<P, F>(&mut self, predicate: P, mut f: F) -> UpdateReport<T>
+        where P: Fn(&T) -> bool,
+              F: FnMut(&mut T)
+    {
+        let mut affected = Vec::new();
+        for &mut (id, ref mut entry) in &mut self.entries {
+            if predicate(entry) {
+                f(entry);
+                affected.push(id);
+            }
+        }
+        UpdateReport { affected, modified: None, diff: None }
+    }
+}
+
+/// Telling whether an entry really changed means comparing it with a copy taken beforehand, so
+/// these need `T: PartialEq + Clone`.
+impl<'a, T: PartialEq + Clone> DBViewMut<'a, T> {
+    /// Like `update`, but the report also says which entries were really modified
+    pub fn update_checked<F>(&mut self, f: F) -> UpdateReport<T>
+        where F: FnMut(&mut T)
+    {
+        self.apply(|_| true, f, false)
+    }
+
+    /// Like `update_where`, but the report also says which entries were really modified
+    pub fn update_where_checked<P, F>(&mut self, predicate: P, f: F) -> UpdateReport<T>
+        where P: Fn(&T) -> bool,
+              F: FnMut(&mut T)
+    {
+        self.apply(predicate, f, false)
+    }
+
+    /// Like `update_checked`, but the report also holds the modified entries before and after
+    pub fn update_with_diff<F>(&mut self, f: F) -> UpdateReport<T>
+        where F: FnMut(&mut T)
+    {
+        self.apply(|_| true, f, true)
+    }
+
+    /// Like `update_where_checked`, but the report also holds the modified entries before and
+    /// after
+    pub fn update_where_with_diff<P, F>(&mut self, predicate: P, f: F) -> UpdateReport<T>
+        where P: Fn(&T) -> bool,
+              F: FnMut(&mut T)
+    {
+        self.apply(predicate, f, true)
+    }
+
+    fn apply<P, F>(&mut self, predicate: P, mut f: F, diff: bool) -> UpdateReport<T>
+        where P: Fn(&T) -> bool,
+              F: FnMut(&mut T)
+    {
+        let mut affected = Vec::new();
+        let mut modified = Vec::new();
+        let mut changes = Vec::new();
+        for &mut (id, ref mut entry) in &mut self.entries {
+            if !predicate(entry) {
+                continue;
+            }
+            let before = entry.clone();
+            f(entry);
+            affected.push(id);
+            if before == **entry {
+                continue;
+            }
+            modified.push(id);
+            if diff {
+                changes.push(Change { id, before, after: entry.clone() });
+            }
+        }
+        let diff = if diff { Some(changes) } else { None };
+        UpdateReport { affected, modified: Some(modified), diff }
+    }
+}
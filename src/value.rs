@@ -0,0 +1,117 @@
+//! Dynamically typed values, as read from the fields of records
+
+use std::fmt;
+
+use FieldType;
+
+/// A single dynamically typed value
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Bytes(Vec<u8>),
+    List(Vec<Value>),
+}
+
+impl Value {
+    /// Returns the type of the value, or `None` for `Null`
+    pub fn field_type(&self) -> Option<FieldType> {
+        match *self {
+            Value::Null => None,
+            Value::Bool(_) => Some(FieldType::Bool),
+            Value::Int(_) => Some(FieldType::Int),
+            Value::Float(_) => Some(FieldType::Float),
+            Value::Str(_) => Some(FieldType::Str),
+            Value::Bytes(_) => Some(FieldType::Bytes),
+            Value::List(_) => Some(FieldType::List),
+        }
+    }
+
+    /// Returns true if `self` is `NULL`
+    pub fn is_null(&self) -> bool {
+        *self == Value::Null
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Value {
+        Value::Bool(b)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(i: i64) -> Value {
+        Value::Int(i)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(x: f64) -> Value {
+        Value::Float(x)
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Value {
+        Value::Str(s)
+    }
+}
+
+impl<'s> From<&'s str> for Value {
+    fn from(s: &'s str) -> Value {
+        Value::Str(s.to_string())
+    }
+}
+
+impl From<Vec<u8>> for Value {
+    fn from(bytes: Vec<u8>) -> Value {
+        Value::Bytes(bytes)
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(list: Vec<Value>) -> Value {
+        Value::List(list)
+    }
+}
+
+impl<V: Into<Value>> From<Option<V>> for Value {
+    fn from(v: Option<V>) -> Value {
+        v.map_or(Value::Null, Into::into)
+    }
+}
+
+/// Prints values the way they are written in the `sql` module, with strings quoted. Bytes are
+/// printed in hex as `X'00ff'` and lists as `[1, 2]`, though queries can't contain either.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Value::Null => write!(f, "NULL"),
+            Value::Bool(true) => write!(f, "TRUE"),
+            Value::Bool(false) => write!(f, "FALSE"),
+            Value::Int(i) => write!(f, "{}", i),
+            Value::Float(x) => write!(f, "{:?}", x),
+            Value::Str(ref s) => write!(f, "'{}'", s.replace('\'', "''")),
+            Value::Bytes(ref bytes) => {
+                write!(f, "X'")?;
+                for byte in bytes {
+                    write!(f, "{:02x}", byte)?;
+                }
+                write!(f, "'")
+            }
+            Value::List(ref values) => {
+                write!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
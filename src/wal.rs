@@ -0,0 +1,421 @@
+//! Durability: keeping a DB on disk as a snapshot plus a write-ahead log of changes since
+//!
+//! A durable DB lives in a directory holding a snapshot, in the binary format, and a log. Each
+//! insert and remove is appended to the log before it is made. Edits made in place, through
+//! `get_mut`, `iter_mut` or a DBViewMut, can't be: they are only known to be finished once the
+//! DB is used mutably again, so they are appended then, or when `flush_edits` is called, or when
+//! the DB is dropped. Until then they would be lost in a crash. Records are written straight to
+//! the file, so they survive the process crashing, though not necessarily the machine losing power
+//! until the next checkpoint.
+//!
+//! The changes made by a transaction, or by one call such as `remove_where`, are kept back until
+//! they are all made, then appended together between a begin and a commit record. Recovering
+//! drops a group with no commit record, so they take effect together or not at all. A transaction
+//! which is rolled back appends nothing.
+//!
+//! The log starts with the magic number `b"wk3L"`, a `u16` version and the schema's fingerprint.
+//! Each record is its length, a CRC-32 of the length and a CRC-32 of its contents, all as `u32`s,
+//! followed by an operation byte. Begin and commit records end there. Other records go on with the id of the
+//! entry, and for inserts and edits the entry's values, laid out as in the binary format. All
+//! integers are little-endian.
+//!
+//! Replaying a record sets the entry with its id to its new value, or removes it, whatever state
+//! the DB was in before. So a log can safely be replayed on top of a snapshot taken after some of
+//! its records were written, which happens if a checkpoint is interrupted.
+
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, Read, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+
+use binary::{self, Decoder};
+use {BinaryError, DB, Record, RowId, TypeError, Value};
+
+const SNAPSHOT: &str = "snapshot.wk3";
+const LOG: &str = "log.wal";
+const MAGIC: &[u8; 4] = b"wk3L";
+const VERSION: u16 = 2;
+const HEADER_LEN: usize = 4 + 2 + 8;
+/// The length of the part of a record before its contents
+const RECORD_HEAD_LEN: usize = 4 + 4 + 4;
+
+const INSERT: u8 = 1;
+const REMOVE: u8 = 2;
+const EDIT: u8 = 3;
+const BEGIN: u8 = 4;
+const COMMIT: u8 = 5;
+
+/// Why a durable DB couldn't be recovered
+#[derive(Debug)]
+pub enum WalError {
+    Io(io::Error),
+    /// The snapshot couldn't be loaded
+    Snapshot(BinaryError),
+    /// The log doesn't start with the magic number
+    BadMagic,
+    /// The log is in a version of the format this version of the crate can't read
+    UnsupportedVersion(u16),
+    /// The log holds records of a different type, whose schema has the fingerprint `found`
+    SchemaMismatch { expected: u64, found: u64 },
+    /// The record at this byte offset in the log is damaged. It is all there, so it can't just
+    /// be a write which was cut short by a crash.
+    CorruptRecord(u64),
+    /// The values in the record at byte `offset` in the log don't make a record
+    Type { offset: u64, error: TypeError },
+}
+
+impl fmt::Display for WalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            WalError::Io(ref e) => write!(f, "{}", e),
+            WalError::Snapshot(ref e) => write!(f, "snapshot: {}", e),
+            WalError::BadMagic => write!(f, "not a wk3 write-ahead log"),
+            WalError::UnsupportedVersion(v) => write!(f, "unsupported log version {}", v),
+            WalError::SchemaMismatch { expected, found } => {
+                write!(f, "log schema fingerprint is {:016x}, expected {:016x}", found, expected)
+            }
+            WalError::CorruptRecord(offset) => {
+                write!(f, "log record at byte {} is corrupt", offset)
+            }
+            WalError::Type { offset, ref error } => {
+                write!(f, "log record at byte {}: {}", offset, error)
+            }
+        }
+    }
+}
+
+impl Error for WalError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            WalError::Io(ref e) => Some(e),
+            WalError::Snapshot(ref e) => Some(e),
+            WalError::Type { ref error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for WalError {
+    fn from(e: io::Error) -> WalError {
+        WalError::Io(e)
+    }
+}
+
+/// The log a durable DB appends its changes to
+pub(crate) struct Wal<T> {
+    dir: PathBuf,
+    file: File,
+    values: fn(&T) -> Vec<Value>,
+    /// Reused to build each record
+    buf: Vec<u8>,
+    /// How many groups of records are open. While any are, records are kept in `pending`.
+    depth: usize,
+    pending: Vec<u8>,
+}
+
+impl<T> Wal<T> {
+    fn new(dir: PathBuf, file: File, values: fn(&T) -> Vec<Value>) -> Wal<T> {
+        Wal { dir, file, values, buf: Vec::new(), depth: 0, pending: Vec::new() }
+    }
+
+    /// Starts a group of records which are appended together by `commit`. Groups can be nested,
+    /// in which case only the outermost is appended, once it is committed.
+    pub(crate) fn begin(&mut self) {
+        self.depth += 1;
+    }
+
+    /// Ends the group started by the last `begin`. If it was the outermost, its records are
+    /// appended, between a begin and a commit record.
+    pub(crate) fn commit(&mut self) {
+        self.depth -= 1;
+        if self.depth > 0 || self.pending.is_empty() {
+            return;
+        }
+        let mut group = mem::take(&mut self.pending);
+        self.record(BEGIN, None, None);
+        group.splice(..0, self.buf.drain(..));
+        self.record(COMMIT, None, None);
+        group.extend_from_slice(&self.buf);
+        self.write(&group);
+        group.clear();
+        self.pending = group;
+    }
+
+    /// Ends the group started by the last `begin`, dropping its records if it was the outermost
+    pub(crate) fn abort(&mut self) {
+        self.depth -= 1;
+        if self.depth == 0 {
+            self.pending.clear();
+        }
+    }
+
+    pub(crate) fn inserted(&mut self, id: RowId, entry: &T) {
+        self.append(INSERT, id, Some(entry));
+    }
+
+    pub(crate) fn removed(&mut self, id: RowId) {
+        self.append(REMOVE, id, None);
+    }
+
+    pub(crate) fn edited(&mut self, id: RowId, entry: &T) {
+        self.append(EDIT, id, Some(entry));
+    }
+
+    /// Appends a record to the log, or to the open group if there is one
+    fn append(&mut self, op: u8, id: RowId, entry: Option<&T>) {
+        self.record(op, Some(id), entry);
+        if self.depth > 0 {
+            self.pending.extend_from_slice(&self.buf);
+        } else {
+            let record = mem::take(&mut self.buf);
+            self.write(&record);
+            self.buf = record;
+        }
+    }
+
+    /// Builds a record in `buf`
+    fn record(&mut self, op: u8, id: Option<RowId>, entry: Option<&T>) {
+        self.buf.clear();
+        self.buf.extend_from_slice(&[0; RECORD_HEAD_LEN]);
+        self.buf.push(op);
+        if let Some(id) = id {
+            self.buf.extend_from_slice(&id.0.to_le_bytes());
+        }
+        if let Some(entry) = entry {
+            for value in (self.values)(entry) {
+                binary::encode_value(&mut self.buf, &value);
+            }
+        }
+        let len = ((self.buf.len() - RECORD_HEAD_LEN) as u32).to_le_bytes();
+        let crc = binary::crc32(&self.buf[RECORD_HEAD_LEN..]);
+        self.buf[..4].copy_from_slice(&len);
+        self.buf[4..8].copy_from_slice(&binary::crc32(&len).to_le_bytes());
+        self.buf[8..12].copy_from_slice(&crc.to_le_bytes());
+    }
+
+    /// Writes records to the log. The changes they describe can't be undone, and carrying on
+    /// without them would lose them in a crash, so this panics if the write fails.
+    fn write(&mut self, records: &[u8]) {
+        if let Err(e) = self.file.write_all(records) {
+            panic!("failed to write to the write-ahead log: {}", e);
+        }
+    }
+}
+
+/// Saves `db` as the snapshot in `dir`. It is written alongside and then moved into place, so
+/// the old snapshot is kept if this fails partway.
+fn write_snapshot<T: Record>(dir: &Path, db: &DB<T>) -> io::Result<()> {
+    let temp = dir.join(format!("{}.tmp", SNAPSHOT));
+    let mut file = File::create(&temp)?;
+    db.save_binary(&mut file)?;
+    file.sync_all()?;
+    fs::rename(&temp, dir.join(SNAPSHOT))
+}
+
+/// Empties `file` down to a header for records of type `T`
+fn reset_log<T: Record>(file: &mut File) -> io::Result<()> {
+    file.set_len(0)?;
+    file.write_all(MAGIC)?;
+    file.write_all(&VERSION.to_le_bytes())?;
+    file.write_all(&T::schema().fingerprint().to_le_bytes())?;
+    file.sync_all()
+}
+
+/// Reads until `buf` is full or the input ends, returning how much was read
+fn read_up_to<R: Read>(input: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match input.read(&mut buf[read..]) {
+            Ok(0) => break,
+            Ok(n) => read += n,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(read)
+}
+
+/// The offset of the begin record of a group being replayed, and the records read since, each
+/// with its offset
+type Group = (u64, Vec<(Vec<u8>, u64)>);
+
+/// Applies the records in `log` to `db`, returning the length of the part of the log which was
+/// whole, or `None` if even the header was cut short
+fn replay<T: Record>(db: &mut DB<T>, log: &File) -> Result<Option<u64>, WalError> {
+    let mut input = BufReader::new(log);
+    let mut header = [0; HEADER_LEN];
+    if read_up_to(&mut input, &mut header)? < HEADER_LEN {
+        return Ok(None);
+    }
+    if header[..4] != MAGIC[..] {
+        return Err(WalError::BadMagic);
+    }
+    let version = u16::from_le_bytes([header[4], header[5]]);
+    if version != VERSION {
+        return Err(WalError::UnsupportedVersion(version));
+    }
+    let expected = T::schema().fingerprint();
+    let mut found = [0; 8];
+    found.copy_from_slice(&header[6..]);
+    let found = u64::from_le_bytes(found);
+    if found != expected {
+        return Err(WalError::SchemaMismatch { expected, found });
+    }
+
+    let mut offset = HEADER_LEN as u64;
+    let mut group: Option<Group> = None;
+    loop {
+        // A group which was never committed is dropped, and so is everything after it
+        let whole = group.as_ref().map_or(offset, |&(begin, _)| begin);
+        // Only a record which runs past the end of the log is taken to have been cut short. One
+        // which is all there but doesn't match its checksums is damaged.
+        let mut head = [0; RECORD_HEAD_LEN];
+        if read_up_to(&mut input, &mut head)? < head.len() {
+            return Ok(Some(whole));
+        }
+        let u32_at = |i: usize| u32::from_le_bytes([head[i], head[i + 1], head[i + 2], head[i + 3]]);
+        let (len, len_crc, crc) = (u32_at(0), u32_at(4), u32_at(8));
+        if binary::crc32(&head[..4]) != len_crc {
+            return Err(WalError::CorruptRecord(offset));
+        }
+        let mut record = Vec::new();
+        // Reading through `take` only allocates as much as is really there, whatever `len` says
+        (&mut input).take(len as u64).read_to_end(&mut record)?;
+        if record.len() < len as usize {
+            return Ok(Some(whole));
+        }
+        if binary::crc32(&record) != crc {
+            return Err(WalError::CorruptRecord(offset));
+        }
+        let next = offset + (RECORD_HEAD_LEN + record.len()) as u64;
+        match (record.first().cloned(), group.take()) {
+            (Some(BEGIN), None) if record.len() == 1 => group = Some((offset, Vec::new())),
+            (Some(COMMIT), Some((_, records))) if record.len() == 1 => {
+                for (record, offset) in records {
+                    apply(db, &record, offset)?;
+                }
+            }
+            (Some(BEGIN), _) | (Some(COMMIT), _) => return Err(WalError::CorruptRecord(offset)),
+            (_, Some((begin, mut records))) => {
+                records.push((record, offset));
+                group = Some((begin, records));
+            }
+            (_, None) => apply(db, &record, offset)?,
+        }
+        offset = next;
+    }
+}
+
+fn apply<T: Record>(db: &mut DB<T>, record: &[u8], offset: u64) -> Result<(), WalError> {
+    let corrupt = || WalError::CorruptRecord(offset);
+    let mut decoder = Decoder::new(record);
+    let op = decoder.byte().ok_or_else(corrupt)?;
+    let id = decoder.u64().ok_or_else(corrupt)?;
+    db.next_id = db.next_id.max(id.checked_add(1).ok_or_else(corrupt)?);
+    let id = RowId(id);
+    match op {
+        INSERT | EDIT => {
+            let mut values = Vec::new();
+            while !decoder.is_empty() {
+                values.push(decoder.value().ok_or_else(corrupt)?);
+            }
+            let entry = T::from_values(values).map_err(|error| WalError::Type { offset, error })?;
+            match db.ids.binary_search(&id) {
+                Ok(i) => db.data[i] = entry,
+                Err(i) => {
+                    db.ids.insert(i, id);
+                    db.data.insert(i, entry);
+                }
+            }
+        }
+        REMOVE if decoder.is_empty() => {
+            if let Ok(i) = db.ids.binary_search(&id) {
+                db.ids.remove(i);
+                db.data.remove(i);
+            }
+        }
+        _ => return Err(corrupt()),
+    }
+    Ok(())
+}
+
+impl<T: Record> DB<T> {
+    /// Makes `self` durable, keeping it in the directory `path`, which is made if it doesn't
+    /// exist. A snapshot of `self` is saved there, replacing any durable DB already in it, and
+    /// from then on every change is logged so it can be recovered with `recover`. Indexes and
+    /// history aren't saved, and have to be set up again after recovering.
+    ///
+    /// # Panics
+    ///
+    /// Once `self` is durable, methods which change it panic if they can't write to the log.
+    pub fn make_durable<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        self.sync_edits();
+        let dir = path.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        write_snapshot(&dir, self)?;
+        let mut file = OpenOptions::new().create(true).append(true).open(dir.join(LOG))?;
+        reset_log::<T>(&mut file)?;
+        self.wal = Some(Wal::new(dir, file, T::values));
+        Ok(())
+    }
+
+    /// Loads the durable DB kept in the directory `path`, replaying the changes in its log on top
+    /// of its snapshot. The DB carries on being durable, logging to the same place.
+    ///
+    /// If the last record in the log was only partly written when the process stopped, it is
+    /// dropped, along with the change it described. A record which is damaged but all there is
+    /// an error, and the log is left as it is.
+    pub fn recover<P: AsRef<Path>>(path: P) -> Result<DB<T>, WalError> {
+        let dir = path.as_ref().to_path_buf();
+        let snapshot = File::open(dir.join(SNAPSHOT))?;
+        let mut db = DB::load_binary(snapshot).map_err(WalError::Snapshot)?;
+        let mut file = OpenOptions::new().read(true).append(true).create(true).open(dir.join(LOG))?;
+        match replay(&mut db, &file)? {
+            Some(len) => file.set_len(len)?,
+            None => reset_log::<T>(&mut file)?,
+        }
+        db.wal = Some(Wal::new(dir, file, T::values));
+        Ok(db)
+    }
+
+    /// Saves a new snapshot of a durable DB and empties its log, so that recovering it is quick
+    ///
+    /// # Errors
+    ///
+    /// Fails with `ErrorKind::InvalidInput` if `self` isn't durable.
+    pub fn checkpoint(&mut self) -> io::Result<()> {
+        self.sync_edits();
+        let dir = match self.wal {
+            Some(ref wal) => wal.dir.clone(),
+            None => return Err(io::Error::new(io::ErrorKind::InvalidInput, "DB is not durable")),
+        };
+        write_snapshot(&dir, self)?;
+        if let Some(ref mut wal) = self.wal {
+            wal.file.set_len(HEADER_LEN as u64)?;
+            wal.file.sync_all()?;
+        }
+        Ok(())
+    }
+}
+
+impl<T> DB<T> {
+    /// Returns true if `self` logs its changes, having been made durable or recovered
+    pub fn is_durable(&self) -> bool {
+        self.wal.is_some()
+    }
+
+    /// Stops logging changes to `self`. What is already on disk is left as it is.
+    pub fn stop_durability(&mut self) {
+        self.sync_edits();
+        self.wal = None;
+    }
+
+    /// Logs the edits made through mutable references since `self` was last used mutably. This
+    /// happens anyway the next time it is, or when it is dropped, but until then they would be
+    /// lost in a crash.
+    pub fn flush_edits(&mut self) {
+        self.sync_edits();
+    }
+}
@@ -0,0 +1,95 @@
+extern crate wk3;
+
+pub use wk3::{Aggregator, DB};
+
+// This macro is an assertion with nicely formatted failure output
+macro_rules! assert_expected_eq_actual {
+    ($a:expr, $b:expr) => ({
+        let (a, b) = (&$a, &$b);
+        assert!(*a == *b,
+                "\nExpected `{:?}` is not equal to Actual `{:?}`\nAssertion: `assert_expected_eq_actual!({}, {})`",
+                *a,
+                *b,
+                stringify!($a),
+                stringify!($b));
+    })
+}
+
+mod aggregate {
+    use super::{Aggregator, DB};
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    struct NoCopy(i32);
+
+    fn is_positive(i: &NoCopy) -> bool { i.0 > 0 }
+
+    fn is_even(i: &NoCopy) -> bool { i.0 % 2 == 0 }
+
+    /// Collects the distinct parities seen, as a tiny example of a custom aggregate
+    struct Parities {
+        even: bool,
+        odd: bool,
+    }
+
+    impl Aggregator<NoCopy> for Parities {
+        type Output = (bool, bool);
+
+        fn accumulate(&mut self, entry: &NoCopy) {
+            if is_even(entry) {
+                self.even = true;
+            } else {
+                self.odd = true;
+            }
+        }
+
+        fn finish(self) -> (bool, bool) {
+            (self.even, self.odd)
+        }
+    }
+
+    #[test]
+    fn on_db() {
+        let x = DB::new((-3..5).map(NoCopy).collect());
+        assert_expected_eq_actual!(4, x.count_where(is_positive));
+        assert_expected_eq_actual!(4, x.sum_by(|i| i.0));
+        assert_expected_eq_actual!(Some(&NoCopy(-3)), x.min_by_key(|i| i.0));
+        assert_expected_eq_actual!(Some(&NoCopy(4)), x.max_by_key(|i| i.0));
+        assert_expected_eq_actual!(Some(0.5), x.mean_by(|i| i.0 as f64));
+        assert_expected_eq_actual!(vec![-3, -2], x.fold(Vec::new(), |mut v, i| {
+            if v.len() < 2 {
+                v.push(i.0);
+            }
+            v
+        }));
+    }
+
+    #[test]
+    fn on_view() {
+        let x = DB::new((-3..5).map(NoCopy).collect());
+        let view = x.select_where(is_positive);
+        assert_expected_eq_actual!(2, view.count_where(is_even));
+        assert_expected_eq_actual!(10i64, view.sum_by(|i| i.0 as i64));
+        assert_expected_eq_actual!(Some(&NoCopy(1)), view.min_by_key(|i| i.0));
+        assert_expected_eq_actual!(Some(2.5), view.mean_by(|i| i.0 as f64));
+        assert_expected_eq_actual!((true, true), view.aggregate(Parities { even: false, odd: false }));
+    }
+
+    #[test]
+    fn min_outlives_view() {
+        let x = DB::new((0..5).map(NoCopy).collect());
+        let largest = {
+            let view = x.select_where(is_even);
+            view.max_by_key(|i| i.0)
+        };
+        assert_expected_eq_actual!(Some(&NoCopy(4)), largest);
+    }
+
+    #[test]
+    fn empty() {
+        let x: DB<NoCopy> = DB::new(Vec::new());
+        assert_expected_eq_actual!(0, x.sum_by(|i| i.0));
+        assert_expected_eq_actual!(None, x.min_by_key(|i| i.0));
+        assert_expected_eq_actual!(None, x.as_view().mean_by(|i| i.0 as f64));
+        assert_expected_eq_actual!((false, false), x.aggregate(Parities { even: false, odd: false }));
+    }
+}
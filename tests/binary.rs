@@ -0,0 +1,222 @@
+#[macro_use]
+extern crate wk3;
+
+pub use wk3::{BinaryError, DB, Record, TypeError, Value};
+// This macro is an assertion with nicely formatted failure output
+macro_rules! assert_expected_eq_actual {
+    ($a:expr, $b:expr) => ({
+        let (a, b) = (&$a, &$b);
+        assert!(*a == *b,
+                "\nExpected `{:?}` is not equal to Actual `{:?}`\nAssertion: `assert_expected_eq_actual!({}, {})`",
+                *a,
+                *b,
+                stringify!($a),
+                stringify!($b));
+    })
+}
+
+mod binary {
+    use super::{BinaryError, DB, Record, TypeError, Value};
+
+    record! {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Person {
+            name: String,
+            age: u8,
+            score: Option<f64>,
+            photo: Vec<u8>,
+            tags: Vec<Value>,
+        }
+    }
+
+    record! {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Renamed {
+            full_name: String,
+            age: u8,
+            score: Option<f64>,
+            photo: Vec<u8>,
+            tags: Vec<Value>,
+        }
+    }
+
+    record! {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Wide {
+            name: String,
+            age: i64,
+            score: Option<f64>,
+            photo: Vec<u8>,
+            tags: Vec<Value>,
+        }
+    }
+
+    fn person(name: &str, age: u8) -> Person {
+        Person {
+            name: name.to_string(),
+            age,
+            score: if age.is_multiple_of(2) { Some(age as f64 / 4.0) } else { None },
+            photo: vec![age, 0, 255],
+            tags: vec![Value::Int(-1),
+                       Value::from("tag"),
+                       Value::List(vec![Value::Bool(true), Value::Null, Value::Float(0.1)])],
+        }
+    }
+
+    fn people() -> DB<Person> {
+        let mut x = DB::new((0..20).map(|i| person(&format!("person {}", i), i)).collect());
+        x.remove_where(|p| p.age.is_multiple_of(3));
+        x
+    }
+
+    fn save<T: Record>(x: &DB<T>) -> Vec<u8> {
+        let mut out = Vec::new();
+        x.save_binary(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn round_trip() {
+        let x = people();
+        let y: DB<Person> = DB::load_binary(&save(&x)[..]).unwrap();
+        assert_expected_eq_actual!(x, y);
+    }
+
+    #[test]
+    fn empty() {
+        let x: DB<Person> = DB::new(Vec::new());
+        let bytes = save(&x);
+        // Just the header and checksum
+        assert_expected_eq_actual!(4 + 2 + 8 + 8 + 8 + 4, bytes.len());
+        assert_expected_eq_actual!(x, DB::load_binary(&bytes[..]).unwrap());
+    }
+
+    #[test]
+    fn ids_are_kept() {
+        let mut x = people();
+        let mut y: DB<Person> = DB::load_binary(&save(&x)[..]).unwrap();
+        assert_expected_eq_actual!(x.insert(person("new", 1)), y.insert(person("new", 1)));
+        assert_expected_eq_actual!(x, y);
+    }
+
+    #[test]
+    fn header() {
+        let bytes = save(&people());
+        assert_expected_eq_actual!(b"wk3\0", &bytes[..4]);
+        assert_expected_eq_actual!([1, 0], [bytes[4], bytes[5]]);
+        let fingerprint = Person::schema().fingerprint().to_le_bytes();
+        assert_expected_eq_actual!(&fingerprint[..], &bytes[6..14]);
+        assert_expected_eq_actual!(&20u64.to_le_bytes()[..], &bytes[14..22]);
+        assert_expected_eq_actual!(&13u64.to_le_bytes()[..], &bytes[22..30]);
+    }
+
+    #[test]
+    fn fingerprints() {
+        assert!(Person::schema().fingerprint() != Renamed::schema().fingerprint());
+        // Both kinds of integer are stored as ints, so the schemas are the same
+        assert_expected_eq_actual!(Person::schema().fingerprint(), Wide::schema().fingerprint());
+    }
+
+    #[test]
+    fn wrong_file() {
+        match DB::<Person>::load_binary(&b"name,age\nbob,3\n"[..]) {
+            Err(BinaryError::BadMagic) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+        let mut bytes = save(&people());
+        bytes[4] = 2;
+        match DB::<Person>::load_binary(&bytes[..]) {
+            Err(BinaryError::UnsupportedVersion(2)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+        match DB::<Renamed>::load_binary(&save(&people())[..]) {
+            Err(BinaryError::SchemaMismatch { expected, found }) => {
+                assert_expected_eq_actual!(Renamed::schema().fingerprint(), expected);
+                assert_expected_eq_actual!(Person::schema().fingerprint(), found);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn truncated() {
+        let bytes = save(&people());
+        for len in &[0, 3, 20, 40, bytes.len() / 2, bytes.len() - 1] {
+            match DB::<Person>::load_binary(&bytes[..*len]) {
+                Err(BinaryError::Truncated) | Err(BinaryError::BadMagic) if *len < 4 => {}
+                Err(BinaryError::Truncated) => {}
+                other => panic!("unexpected result {:?} for length {}", other, len),
+            }
+        }
+        // A huge row length is only believed as far as the file goes
+        let mut bytes = bytes[..34].to_vec();
+        bytes[30..34].copy_from_slice(&u32::MAX.to_le_bytes());
+        match DB::<Person>::load_binary(&bytes[..]) {
+            Err(BinaryError::Truncated) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn corrupt() {
+        let bytes = save(&people());
+        let name = bytes.windows(9).position(|w| w == b"person 10").unwrap();
+        let mut flipped = bytes.clone();
+        flipped[name] = b'P';
+        match DB::<Person>::load_binary(&flipped[..]) {
+            Err(BinaryError::ChecksumMismatch { .. }) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        match DB::<Person>::load_binary(&trailing[..]) {
+            Err(BinaryError::TrailingData) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        // The first row's first value is a string, whose tag follows the row's length and id
+        let mut bad_tag = bytes.clone();
+        bad_tag[30 + 4 + 8] = 9;
+        match DB::<Person>::load_binary(&bad_tag[..]) {
+            Err(BinaryError::CorruptRow(0)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn type_errors() {
+        let x = DB::new(vec![Wide {
+            name: "big".to_string(),
+            age: 1000,
+            score: None,
+            photo: Vec::new(),
+            tags: Vec::new(),
+        }]);
+        let mut bytes = save(&x);
+        // Pretend the file holds people, fixing the checksum up to match
+        let fingerprint = Person::schema().fingerprint().to_le_bytes();
+        bytes[6..14].copy_from_slice(&fingerprint);
+        let len = bytes.len();
+        bytes.truncate(len - 4);
+        let sum = crc32(&bytes);
+        bytes.extend_from_slice(&sum.to_le_bytes());
+        match DB::<Person>::load_binary(&bytes[..]) {
+            Err(BinaryError::Type { row: 0, error: TypeError::OutOfRange { ref field, .. } }) => {
+                assert_expected_eq_actual!("age", field);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    fn crc32(bytes: &[u8]) -> u32 {
+        let mut crc = !0u32;
+        for &b in bytes {
+            crc ^= b as u32;
+            for _ in 0..8 {
+                crc = if crc & 1 == 1 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+            }
+        }
+        !crc
+    }
+}
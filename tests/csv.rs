@@ -0,0 +1,229 @@
+#[macro_use]
+extern crate wk3;
+
+pub use wk3::{CsvErrorKind, CsvFormat, DB, TypeError, Value};
+// This macro is an assertion with nicely formatted failure output
+macro_rules! assert_expected_eq_actual {
+    ($a:expr, $b:expr) => ({
+        let (a, b) = (&$a, &$b);
+        assert!(*a == *b,
+                "\nExpected `{:?}` is not equal to Actual `{:?}`\nAssertion: `assert_expected_eq_actual!({}, {})`",
+                *a,
+                *b,
+                stringify!($a),
+                stringify!($b));
+    })
+}
+
+mod csv {
+    use super::{CsvErrorKind, CsvFormat, DB, TypeError, Value};
+
+    record! {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Person {
+            name: String,
+            age: u8,
+            score: Option<f64>,
+            active: bool,
+        }
+    }
+
+    record! {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Blob {
+            id: i64,
+            data: Vec<u8>,
+            note: Option<String>,
+        }
+    }
+
+    record! {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Note {
+            note: Option<String>,
+        }
+    }
+
+    fn person(name: &str, age: u8, score: Option<f64>) -> Person {
+        Person { name: name.to_string(), age, score, active: age < 40 }
+    }
+
+    fn people() -> DB<Person> {
+        DB::new(vec![person("alice", 30, Some(1.5)),
+                     person("bob, jr", 25, None),
+                     person("carol \"cc\"", 41, Some(-3.0)),
+                     person("dan\nsmith", 19, Some(0.25)),
+                     person("", 50, None)])
+    }
+
+    fn to_csv(x: &DB<Person>, format: &CsvFormat) -> String {
+        let mut out = Vec::new();
+        x.as_view().to_csv_with(&mut out, format).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn error(input: &str) -> (usize, CsvErrorKind) {
+        let e = DB::<Person>::from_csv(input.as_bytes()).unwrap_err();
+        (e.line(), e.into_kind())
+    }
+
+    #[test]
+    fn write() {
+        let expected = "name,age,score,active\n\
+                        alice,30,1.5,true\n\
+                        \"bob, jr\",25,,true\n\
+                        \"carol \"\"cc\"\"\",41,-3,false\n\
+                        \"dan\nsmith\",19,0.25,true\n\
+                        \"\",50,,false\n";
+        assert_expected_eq_actual!(expected, to_csv(&people(), &CsvFormat::new()));
+    }
+
+    #[test]
+    fn round_trip() {
+        let x = people();
+        let mut out = Vec::new();
+        x.as_view().to_csv(&mut out).unwrap();
+        let y: DB<Person> = DB::from_csv(&out[..]).unwrap();
+        assert_expected_eq_actual!(x, y);
+    }
+
+    #[test]
+    fn round_trip_with_format() {
+        let x = people();
+        let format = CsvFormat::new().delimiter(';').headers(false);
+        let text = to_csv(&x, &format);
+        assert!(text.starts_with("alice;30;1.5;true\nbob, jr;25;;true\n"));
+        assert_expected_eq_actual!(x, DB::from_csv_with(text.as_bytes(), &format).unwrap());
+
+        let tabs = CsvFormat::new().delimiter('\t');
+        let text = to_csv(&x, &tabs);
+        assert_expected_eq_actual!(x, DB::from_csv_with(text.as_bytes(), &tabs).unwrap());
+    }
+
+    #[test]
+    fn view_order_is_kept() {
+        let x = people();
+        let mut out = Vec::new();
+        x.select_where(|p| p.score.is_some())
+            .order_by(|p: &Person| p.age)
+            .to_csv_with(&mut out, &CsvFormat::new().headers(false))
+            .unwrap();
+        let y: DB<Person> = DB::from_csv_with(&out[..], &CsvFormat::new().headers(false)).unwrap();
+        let names: Vec<String> = y.into_iter().map(|p| p.name).collect();
+        assert_expected_eq_actual!(vec!["dan\nsmith", "alice", "carol \"cc\""], names);
+    }
+
+    #[test]
+    fn columns_in_any_order() {
+        let input = "active,score,name,age\r\ntrue,,erin,22\r\n\r\nFALSE,2,\"frank\",60\r\n";
+        let x: DB<Person> = DB::from_csv(input.as_bytes()).unwrap();
+        let people: Vec<Person> = x.into_iter().collect();
+        let mut frank = person("frank", 60, Some(2.0));
+        frank.active = false;
+        assert_expected_eq_actual!(vec![person("erin", 22, None), frank], people);
+    }
+
+    #[test]
+    fn bytes_and_nullable_strings() {
+        let x = DB::new(vec![Blob { id: 1, data: vec![0, 255, 16], note: Some(String::new()) },
+                             Blob { id: 2, data: Vec::new(), note: None }]);
+        let mut out = Vec::new();
+        x.as_view().to_csv(&mut out).unwrap();
+        assert_expected_eq_actual!("id,data,note\n1,00ff10,\"\"\n2,\"\",\n",
+                                   String::from_utf8(out.clone()).unwrap());
+        assert_expected_eq_actual!(x, DB::from_csv(&out[..]).unwrap());
+    }
+
+    #[test]
+    fn single_nullable_column() {
+        let x = DB::new(vec![Note { note: None },
+                             Note { note: Some("x".to_string()) },
+                             Note { note: None }]);
+        for format in &[CsvFormat::new(), CsvFormat::new().headers(false)] {
+            let mut out = Vec::new();
+            format.write(&x.as_view(), &mut out).unwrap();
+            // A row whose only field is `NULL` is a blank line, which isn't skipped
+            assert!(String::from_utf8(out.clone()).unwrap().ends_with("\nx\n\n"));
+            assert_expected_eq_actual!(x, format.read(&out[..]).unwrap());
+        }
+    }
+
+    #[test]
+    fn empty_input() {
+        let x: DB<Person> = DB::from_csv(&b""[..]).unwrap();
+        assert!(x.is_empty());
+        let y: DB<Person> = DB::from_csv(&b"name,age,score,active\n"[..]).unwrap();
+        assert!(y.is_empty());
+    }
+
+    #[test]
+    fn bad_values() {
+        let input = "name,age,score,active\nalice,30,1.5,true\nbob,old,,true\n";
+        match error(input) {
+            (3, CsvErrorKind::BadValue { ref field, ref text }) => {
+                assert_expected_eq_actual!(("age", "old"), (&field[..], &text[..]));
+            }
+            other => panic!("unexpected error {:?}", other),
+        }
+        match error("name,age,score,active\n\"a\nb\",30,1.5,true\ncarol,300,,true\n") {
+            (4, CsvErrorKind::Type(TypeError::OutOfRange { ref field, ref value })) => {
+                assert_expected_eq_actual!(("age", &Value::Int(300)), (&field[..], value));
+            }
+            other => panic!("unexpected error {:?}", other),
+        }
+        match error("name,age,score,active\nalice,,1.5,true\n") {
+            (2, CsvErrorKind::Type(TypeError::WrongType { found: None, .. })) => {}
+            other => panic!("unexpected error {:?}", other),
+        }
+        match error("name,age,score,active\nalice,30,1.5,maybe\n") {
+            (2, CsvErrorKind::BadValue { .. }) => {}
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+
+    #[test]
+    fn bad_rows() {
+        match error("name,age,score,active\nalice,30,1.5\n") {
+            (2, CsvErrorKind::WrongLength { expected: 4, found: 3 }) => {}
+            other => panic!("unexpected error {:?}", other),
+        }
+        match error("name,age,score,active\nalice,30,,true\n\"bob,25,,true\n") {
+            (3, CsvErrorKind::UnclosedQuote) => {}
+            other => panic!("unexpected error {:?}", other),
+        }
+        match error("name,age,score,active\n\"bob\"x,25,,true\n") {
+            (2, CsvErrorKind::TextAfterQuote) => {}
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+
+    #[test]
+    fn bad_headers() {
+        match error("name,age,height,active\n") {
+            (1, CsvErrorKind::UnknownColumn(ref name)) => assert_eq!("height", name),
+            other => panic!("unexpected error {:?}", other),
+        }
+        match error("name,age,score\n") {
+            (1, CsvErrorKind::MissingColumn(ref name)) => assert_eq!("active", name),
+            other => panic!("unexpected error {:?}", other),
+        }
+        match error("name,age,score,active,age\n") {
+            (1, CsvErrorKind::DuplicateColumn(ref name)) => assert_eq!("age", name),
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+
+    #[test]
+    fn error_messages() {
+        let input = "name,age,score,active\nbob,old,,true\n";
+        let e = DB::<Person>::from_csv(input.as_bytes()).unwrap_err();
+        assert_expected_eq_actual!("invalid value \"old\" for field `age` at line 2",
+                                   e.to_string());
+    }
+
+    #[test]
+    #[should_panic]
+    fn quote_delimiter_panics() {
+        CsvFormat::new().delimiter('"');
+    }
+}
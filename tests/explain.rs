@@ -0,0 +1,117 @@
+extern crate wk3;
+
+pub use wk3::{DB, Operation};
+
+// This macro is an assertion with nicely formatted failure output
+macro_rules! assert_expected_eq_actual {
+    ($a:expr, $b:expr) => ({
+        let (a, b) = (&$a, &$b);
+        assert!(*a == *b,
+                "\nExpected `{:?}` is not equal to Actual `{:?}`\nAssertion: `assert_expected_eq_actual!({}, {})`",
+                *a,
+                *b,
+                stringify!($a),
+                stringify!($b));
+    })
+}
+
+mod explain {
+    use super::{DB, Operation};
+    use wk3::predicate::named;
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    struct Person {
+        name: String,
+        age: u32,
+    }
+
+    fn person(name: &str, age: u32) -> Person {
+        Person { name: name.to_string(), age }
+    }
+
+    fn db() -> DB<Person> {
+        DB::new(vec![person("alice", 30),
+                     person("bob", 25),
+                     person("carol", 41),
+                     person("dave", 30),
+                     person("erin", 19)])
+    }
+
+    fn operations(plan: &wk3::Plan) -> Vec<Operation> {
+        plan.steps().map(|step| step.operation().clone()).collect()
+    }
+
+    #[test]
+    fn full_scan() {
+        let x = db();
+        let plan = x.query()
+            .filter_by(named("age >= 25", |p: &Person| p.age >= 25))
+            .order_by(|p| p.age)
+            .offset(1)
+            .limit(2)
+            .explain();
+        assert_expected_eq_actual!(vec![Operation::Limit(2),
+                                        Operation::Offset(1),
+                                        Operation::Sort { keys: 1 },
+                                        Operation::Filter { condition: "age >= 25".to_string() },
+                                        Operation::Scan],
+                                   operations(&plan));
+        let estimates: Vec<usize> = plan.steps().map(|step| step.estimated_rows()).collect();
+        assert_expected_eq_actual!(vec![2, 4, 5, 5, 5], estimates);
+        assert!(plan.steps().all(|step| step.actual().is_none()));
+    }
+
+    #[test]
+    fn index_lookup() {
+        let mut x = db();
+        x.create_index("age", |p: &Person| p.age);
+        x.create_ordered_index("by_age", |p: &Person| p.age);
+        let plan = x.query().filter_range("by_age", 20u32..).filter_eq("age", 30u32).explain();
+        let lookup = Operation::IndexLookup {
+            index: "age".to_string(),
+            condition: "age = 30".to_string(),
+        };
+        let filter = Operation::Filter { condition: "by_age in 20..".to_string() };
+        assert_expected_eq_actual!(vec![filter, lookup], operations(&plan));
+        assert_expected_eq_actual!(2, plan.estimated_rows());
+    }
+
+    #[test]
+    fn analyze_counts_rows() {
+        let mut x = db();
+        x.create_index("age", |p: &Person| p.age);
+        let query = x.query()
+            .filter_eq("age", 30u32)
+            .filter_by(named("not alice", |p: &Person| p.name != "alice"));
+        let plan = query.explain_analyze();
+        let rows: Vec<usize> = plan.steps().map(|step| step.actual().unwrap().rows).collect();
+        assert_expected_eq_actual!(vec![1, 2], rows);
+        assert_expected_eq_actual!(query.explain().estimated_rows(), plan.estimated_rows());
+    }
+
+    #[test]
+    fn analyze_stops_at_limit() {
+        let x = db();
+        let plan = x.query().limit(2).explain_analyze();
+        let rows: Vec<usize> = plan.steps().map(|step| step.actual().unwrap().rows).collect();
+        assert_expected_eq_actual!(vec![2, 2], rows);
+    }
+
+    #[test]
+    fn display() {
+        let mut x = db();
+        x.create_index("age", |p: &Person| p.age);
+        let query = x.query()
+            .filter_eq("age", 30u32)
+            .filter_by(named("not alice", |p: &Person| p.name != "alice"))
+            .limit(10);
+        assert_expected_eq_actual!("Limit 10  [estimated rows: 2]\n  \
+                                    -> Filter (not alice)  [estimated rows: 2]\n    \
+                                    -> Index Lookup using age (age = 30)  [estimated rows: 2]",
+                                   query.explain().to_string());
+        let analyzed = query.explain_analyze().to_string();
+        assert!(analyzed.starts_with("Limit 10  [estimated rows: 2, actual rows: 1, time: "),
+                "{}",
+                analyzed);
+    }
+}
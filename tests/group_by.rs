@@ -0,0 +1,91 @@
+extern crate wk3;
+
+pub use wk3::{DB, filter_one, filter_two};
+
+// This macro is an assertion with nicely formatted failure output
+macro_rules! assert_expected_eq_actual {
+    ($a:expr, $b:expr) => ({
+        let (a, b) = (&$a, &$b);
+        assert!(*a == *b,
+                "\nExpected `{:?}` is not equal to Actual `{:?}`\nAssertion: `assert_expected_eq_actual!({}, {})`",
+                *a,
+                *b,
+                stringify!($a),
+                stringify!($b));
+    })
+}
+
+mod group_by {
+    use super::{DB, filter_one, filter_two};
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    struct Order {
+        user: &'static str,
+        cents: i64,
+    }
+
+    fn orders() -> Vec<Order> {
+        vec![Order { user: "bob", cents: 300 },
+             Order { user: "alice", cents: 100 },
+             Order { user: "bob", cents: 50 },
+             Order { user: "alice", cents: 700 },
+             Order { user: "carol", cents: 20 }]
+    }
+
+    #[test]
+    fn groups_in_key_order() {
+        let x = DB::new(orders());
+        let groups = x.as_view().group_by(|o| o.user);
+        assert_expected_eq_actual!(vec!["alice", "bob", "carol"], groups.keys().cloned().collect::<Vec<_>>());
+        assert_expected_eq_actual!(vec![300, 50],
+                                   groups.get(&"bob").unwrap().iter().map(|o| o.cents).collect::<Vec<_>>());
+        assert!(groups.get(&"dave").is_none());
+    }
+
+    #[test]
+    fn aggregates_per_group() {
+        let x = DB::new(orders());
+        let groups = x.as_view().group_by(|o| o.user);
+        assert_expected_eq_actual!(2, groups.counts()[&"alice"]);
+        assert_expected_eq_actual!(800, groups.sum_by(|o| o.cents)[&"alice"]);
+        assert_expected_eq_actual!(175.0, groups.mean_by(|o| o.cents as f64)[&"bob"]);
+        assert_expected_eq_actual!(&Order { user: "bob", cents: 50 },
+                                   groups.min_by_key(|o| o.cents)[&"bob"]);
+        assert_expected_eq_actual!(&Order { user: "alice", cents: 700 },
+                                   groups.max_by_key(|o| o.cents)[&"alice"]);
+    }
+
+    #[test]
+    fn groups_outlive_source_view() {
+        let x = DB::new(orders());
+        let bobs = {
+            let view = x.select_where(|o| o.cents > 10);
+            let mut groups = view.group_by(|o| o.user).into_iter();
+            groups.nth(1).unwrap().1
+        };
+        let big = filter_one(&bobs, |o| o.cents > 100);
+        assert_expected_eq_actual!(1, big.len());
+    }
+
+    #[test]
+    fn groups_from_two_dbs() {
+        let x = DB::new(orders());
+        let alice = x.as_view().group_by(|o| o.user).into_iter().next().unwrap().1;
+        let carol = {
+            let y = DB::new(orders());
+            let carol = y.as_view().group_by(|o| o.user).into_iter().nth(2).unwrap().1;
+            let (_, carol) = filter_two(&alice, &carol, |o| o.cents > 0);
+            carol.len()
+        };
+        assert_expected_eq_actual!(1, carol);
+        assert_expected_eq_actual!(2, alice.len());
+    }
+
+    #[test]
+    fn empty_view() {
+        let x: DB<Order> = DB::new(Vec::new());
+        let groups = x.as_view().group_by(|o| o.user);
+        assert!(groups.is_empty());
+        assert!(groups.counts().is_empty());
+    }
+}
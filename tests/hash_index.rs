@@ -0,0 +1,122 @@
+extern crate wk3;
+
+pub use wk3::DB;
+
+// This macro is an assertion with nicely formatted failure output
+macro_rules! assert_expected_eq_actual {
+    ($a:expr, $b:expr) => ({
+        let (a, b) = (&$a, &$b);
+        assert!(*a == *b,
+                "\nExpected `{:?}` is not equal to Actual `{:?}`\nAssertion: `assert_expected_eq_actual!({}, {})`",
+                *a,
+                *b,
+                stringify!($a),
+                stringify!($b));
+    })
+}
+
+mod hash_index {
+    use super::DB;
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    struct Person {
+        name: String,
+        age: u32,
+    }
+
+    fn person(name: &str, age: u32) -> Person {
+        Person { name: name.to_string(), age }
+    }
+
+    fn db() -> DB<Person> {
+        let mut x = DB::new(vec![person("alice", 30), person("bob", 25), person("carol", 30)]);
+        x.create_index("age", |p: &Person| p.age);
+        x
+    }
+
+    fn names<'a>(view: wk3::DBView<'a, Person>) -> Vec<&'a str> {
+        view.into_iter().map(|p| &p.name[..]).collect()
+    }
+
+    #[test]
+    fn select_eq() {
+        let x = db();
+        assert_expected_eq_actual!(vec!["alice", "carol"], names(x.select_eq("age", &30u32)));
+        assert_expected_eq_actual!(vec!["bob"], names(x.select_eq("age", &25u32)));
+        assert!(x.select_eq("age", &99u32).is_empty());
+        assert_expected_eq_actual!(x.select_where(|p| p.age == 30), x.select_eq("age", &30u32));
+    }
+
+    #[test]
+    fn insert_and_remove() {
+        let mut x = db();
+        let dave = x.insert(person("dave", 25));
+        assert_expected_eq_actual!(vec!["bob", "dave"], names(x.select_eq("age", &25u32)));
+        x.remove(dave);
+        x.remove_where(|p| p.name == "alice");
+        assert_expected_eq_actual!(vec!["bob"], names(x.select_eq("age", &25u32)));
+        assert_expected_eq_actual!(vec!["carol"], names(x.select_eq("age", &30u32)));
+        x.retain(|p| p.age != 30);
+        assert!(x.select_eq("age", &30u32).is_empty());
+    }
+
+    #[test]
+    fn edits_through_view_mut() {
+        let mut x = db();
+        for p in x.select_where_mut(|p| p.name == "alice") {
+            p.age = 25;
+        }
+        // Checked before anything else touches the DB, while alice's index entry is still stale
+        assert_expected_eq_actual!(vec!["alice", "bob"], names(x.select_eq("age", &25u32)));
+        assert_expected_eq_actual!(vec!["carol"], names(x.select_eq("age", &30u32)));
+        x.insert(person("dave", 40));
+        assert_expected_eq_actual!(vec!["alice", "bob"], names(x.select_eq("age", &25u32)));
+    }
+
+    #[test]
+    fn edits_through_get_mut_and_iter_mut() {
+        let mut x = db();
+        let bob = x.select_eq("age", &25u32).rows().next().unwrap().0;
+        x.get_mut(bob).unwrap().age = 30;
+        assert_expected_eq_actual!(vec!["alice", "bob", "carol"], names(x.select_eq("age", &30u32)));
+        for p in &mut x {
+            p.age += 1;
+        }
+        assert!(x.select_eq("age", &30u32).is_empty());
+        assert_expected_eq_actual!(3, x.select_eq("age", &31u32).len());
+    }
+
+    #[test]
+    fn refresh_indexes() {
+        let mut x = db();
+        for p in x.as_view_mut() {
+            p.age += 1;
+        }
+        x.refresh_indexes();
+        assert_expected_eq_actual!(vec!["bob"], names(x.select_eq("age", &26u32)));
+        assert_expected_eq_actual!(vec!["alice", "carol"], names(x.select_eq("age", &31u32)));
+        assert!(x.select_eq("age", &30u32).is_empty());
+    }
+
+    #[test]
+    fn several_indexes() {
+        let mut x = db();
+        x.create_index("name", |p: &Person| p.name.clone());
+        assert_expected_eq_actual!(vec!["carol"], names(x.select_eq("name", &"carol".to_string())));
+        assert!(x.drop_index("age"));
+        assert!(!x.has_index("age"));
+        assert!(x.has_index("name"));
+    }
+
+    #[test]
+    #[should_panic]
+    fn missing_index() {
+        db().select_eq("height", &30u32);
+    }
+
+    #[test]
+    #[should_panic]
+    fn wrong_key_type() {
+        db().select_eq("age", &30u64);
+    }
+}
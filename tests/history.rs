@@ -0,0 +1,155 @@
+extern crate wk3;
+
+pub use wk3::DB;
+
+// This macro is an assertion with nicely formatted failure output
+macro_rules! assert_expected_eq_actual {
+    ($a:expr, $b:expr) => ({
+        let (a, b) = (&$a, &$b);
+        assert!(*a == *b,
+                "\nExpected `{:?}` is not equal to Actual `{:?}`\nAssertion: `assert_expected_eq_actual!({}, {})`",
+                *a,
+                *b,
+                stringify!($a),
+                stringify!($b));
+    })
+}
+
+mod history {
+    use super::DB;
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    struct NoCopy(i32);
+
+    fn db() -> DB<NoCopy> {
+        let mut x = DB::new(vec![NoCopy(1), NoCopy(2), NoCopy(3)]);
+        x.enable_history(1 << 20);
+        x
+    }
+
+    fn values(x: &DB<NoCopy>) -> Vec<i32> {
+        x.iter().map(|n| n.0).collect()
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut x = db();
+        let id = x.insert(NoCopy(4));
+        x.remove_where(|n| n.0 % 2 == 1);
+        for n in x.as_view_mut() {
+            n.0 *= 10;
+        }
+        assert_expected_eq_actual!(vec![20, 40], values(&x));
+        assert_expected_eq_actual!(3, x.history_len());
+
+        assert!(x.undo());
+        assert_expected_eq_actual!(vec![2, 4], values(&x));
+        assert!(x.undo());
+        assert_expected_eq_actual!(vec![1, 2, 3, 4], values(&x));
+        assert!(x.undo());
+        assert_expected_eq_actual!(vec![1, 2, 3], values(&x));
+        assert!(!x.undo());
+        assert_expected_eq_actual!(3, x.redo_len());
+
+        assert!(x.redo());
+        assert_expected_eq_actual!(Some(&NoCopy(4)), x.get(id));
+        assert!(x.redo());
+        assert!(x.redo());
+        assert!(!x.redo());
+        assert_expected_eq_actual!(vec![20, 40], values(&x));
+    }
+
+    #[test]
+    fn undo_restores_ids_and_positions() {
+        let mut x = db();
+        let before: Vec<_> = x.as_view().rows().map(|(id, n)| (id, n.clone())).collect();
+        let middle = before[1].0;
+        x.remove(middle);
+        x.undo();
+        let after: Vec<_> = x.as_view().rows().map(|(id, n)| (id, n.clone())).collect();
+        assert_expected_eq_actual!(before, after);
+    }
+
+    #[test]
+    fn unchanged_edits_are_not_recorded() {
+        let mut x = db();
+        for n in x.iter_mut() {
+            n.0 += 0;
+        }
+        x.insert(NoCopy(4));
+        assert_expected_eq_actual!(1, x.history_len());
+    }
+
+    #[test]
+    fn pending_edits_are_counted() {
+        let mut x = db();
+        let id = x.as_view().rows().next().unwrap().0;
+        x.get_mut(id).unwrap().0 = 100;
+        assert_expected_eq_actual!(1, x.history_len());
+        x.undo();
+        assert_expected_eq_actual!(vec![1, 2, 3], values(&x));
+    }
+
+    #[test]
+    fn new_change_clears_redo() {
+        let mut x = db();
+        x.insert(NoCopy(4));
+        x.undo();
+        assert_expected_eq_actual!(1, x.redo_len());
+        x.insert(NoCopy(5));
+        assert_expected_eq_actual!(0, x.redo_len());
+        assert!(!x.redo());
+    }
+
+    #[test]
+    fn memory_cap_drops_oldest_steps() {
+        let mut x = DB::new(vec![NoCopy(1)]);
+        x.enable_history_with(3, |_: &NoCopy| 1);
+        for i in 2..10 {
+            x.insert(NoCopy(i));
+        }
+        assert_expected_eq_actual!(3, x.history_len());
+        while x.undo() {}
+        assert_expected_eq_actual!(vec![1, 2, 3, 4, 5, 6], values(&x));
+    }
+
+    #[test]
+    fn transactions_are_one_step() {
+        let mut x = db();
+        let committed: Result<(), ()> = x.transaction(|tx| {
+            tx.insert(NoCopy(4));
+            let first = tx.as_view().rows().next().unwrap().0;
+            tx.get_mut(first).unwrap().0 = 100;
+            Ok(())
+        });
+        assert!(committed.is_ok());
+        let failed: Result<(), ()> = x.transaction(|tx| {
+            tx.insert(NoCopy(5));
+            Err(())
+        });
+        assert!(failed.is_err());
+        assert_expected_eq_actual!(1, x.history_len());
+        x.undo();
+        assert_expected_eq_actual!(vec![1, 2, 3], values(&x));
+    }
+
+    #[test]
+    fn indexes_follow_undo() {
+        let mut x = db();
+        x.create_index("value", |n: &NoCopy| n.0);
+        for n in x.as_view_mut() {
+            n.0 = 7;
+        }
+        x.undo();
+        assert_expected_eq_actual!(1, x.select_eq("value", &2).len());
+        assert!(x.select_eq("value", &7).is_empty());
+    }
+
+    #[test]
+    fn disabled_by_default() {
+        let mut x = DB::new(vec![NoCopy(1)]);
+        x.insert(NoCopy(2));
+        assert_expected_eq_actual!(0, x.history_len());
+        assert!(!x.undo());
+    }
+}
@@ -0,0 +1,110 @@
+extern crate wk3;
+
+pub use wk3::DB;
+
+// This macro is an assertion with nicely formatted failure output
+macro_rules! assert_expected_eq_actual {
+    ($a:expr, $b:expr) => ({
+        let (a, b) = (&$a, &$b);
+        assert!(*a == *b,
+                "\nExpected `{:?}` is not equal to Actual `{:?}`\nAssertion: `assert_expected_eq_actual!({}, {})`",
+                *a,
+                *b,
+                stringify!($a),
+                stringify!($b));
+    })
+}
+
+mod iterators {
+    mod db {
+        use super::super::DB;
+
+        #[test]
+        fn iter_is_exact_and_reversible() {
+            let x = DB::new(vec![1, 2, 3]);
+            let mut it = x.iter();
+            assert_expected_eq_actual!((3, Some(3)), it.size_hint());
+            assert_expected_eq_actual!(Some(&3), it.next_back());
+            assert_expected_eq_actual!(2, it.len());
+            assert_expected_eq_actual!(vec![1, 2], it.cloned().collect::<Vec<_>>());
+        }
+
+        #[test]
+        fn iter_mut_modifies_in_place() {
+            let mut x = DB::new(vec![1, 2, 3]);
+            for i in &mut x {
+                *i *= 10;
+            }
+            assert_expected_eq_actual!(vec![10, 20, 30], x.iter().cloned().collect::<Vec<_>>());
+        }
+
+        #[test]
+        fn into_iter_rev() {
+            let x = DB::new(vec![1, 2, 3]);
+            assert_expected_eq_actual!(vec![3, 2, 1], x.into_iter().rev().collect::<Vec<_>>());
+        }
+
+        #[test]
+        fn fused() {
+            let x = DB::new(vec![1]);
+            let mut it = x.iter();
+            assert_expected_eq_actual!(Some(&1), it.next());
+            assert_expected_eq_actual!(None, it.next());
+            assert_expected_eq_actual!(None, it.next());
+        }
+    }
+
+    mod view {
+        use super::super::DB;
+
+        #[test]
+        fn iter_outlives_view() {
+            let x = DB::new(vec![-1, 2, -3, 4]);
+            let refs: Vec<&i32> = {
+                let view = x.select_where(|i| *i > 0);
+                view.iter().collect()
+            };
+            assert_expected_eq_actual!(vec![&2, &4], refs);
+        }
+
+        #[test]
+        fn adapters_without_collecting() {
+            let x = DB::new((0..10).collect());
+            let view = x.select_where(|i| i % 2 == 0);
+            assert_expected_eq_actual!(5, view.iter().len());
+            assert_expected_eq_actual!(20, view.iter().sum::<i32>());
+            assert_expected_eq_actual!(Some(&8), view.iter().next_back());
+            assert_expected_eq_actual!(vec![&0, &2], (&view).into_iter().take(2).collect::<Vec<_>>());
+        }
+    }
+
+    mod view_mut {
+        use super::super::DB;
+
+        #[test]
+        fn iter_mut_then_iter() {
+            let mut x = DB::new(vec![1, 2, 3, 4]);
+            {
+                let mut view = x.select_where_mut(|i| i % 2 == 0);
+                for i in view.iter_mut() {
+                    *i = -*i;
+                }
+                assert_expected_eq_actual!(vec![&-2, &-4], view.iter().collect::<Vec<_>>());
+                assert_expected_eq_actual!((2, Some(2)), view.iter().size_hint());
+            }
+            assert_expected_eq_actual!(vec![1, -2, 3, -4], x.into_iter().collect::<Vec<_>>());
+        }
+
+        #[test]
+        fn into_iter_rev() {
+            let mut x = DB::new(vec![1, 2, 3]);
+            let mut seen = Vec::new();
+            for i in x.as_view_mut().into_iter().rev() {
+                seen.push(*i);
+                *i = 0;
+            }
+            assert_expected_eq_actual!(vec![3, 2, 1], seen);
+            assert_expected_eq_actual!(vec![0, 0, 0], x.into_iter().collect::<Vec<_>>());
+        }
+    }
+}
@@ -0,0 +1,96 @@
+extern crate wk3;
+
+pub use wk3::DB;
+
+// This macro is an assertion with nicely formatted failure output
+macro_rules! assert_expected_eq_actual {
+    ($a:expr, $b:expr) => ({
+        let (a, b) = (&$a, &$b);
+        assert!(*a == *b,
+                "\nExpected `{:?}` is not equal to Actual `{:?}`\nAssertion: `assert_expected_eq_actual!({}, {})`",
+                *a,
+                *b,
+                stringify!($a),
+                stringify!($b));
+    })
+}
+
+mod join {
+    use super::DB;
+    use wk3::{join, hash_join, nested_loop_join, left_join, nested_loop_left_join, cross_join};
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    struct User {
+        id: u32,
+        name: &'static str,
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    struct Order {
+        user: u32,
+        cents: i64,
+    }
+
+    fn users() -> Vec<User> {
+        vec![User { id: 1, name: "alice" }, User { id: 2, name: "bob" }, User { id: 3, name: "carol" }]
+    }
+
+    fn orders() -> Vec<Order> {
+        vec![Order { user: 2, cents: 10 },
+             Order { user: 1, cents: 20 },
+             Order { user: 2, cents: 30 },
+             Order { user: 4, cents: 40 }]
+    }
+
+    #[test]
+    fn inner_join() {
+        let u = DB::new(users());
+        let o = DB::new(orders());
+        let joined = join(&u.as_view(), &o.as_view(), |u| u.id, |o| o.user);
+        let expected = vec![("alice", 20), ("bob", 10), ("bob", 30)];
+        assert_expected_eq_actual!(expected,
+                                   joined.iter().map(|(u, o)| (u.name, o.cents)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn strategies_agree() {
+        let u = DB::new(users());
+        let o = DB::new(orders());
+        let (uv, ov) = (u.as_view(), o.as_view());
+        assert_expected_eq_actual!(hash_join(&uv, &ov, |u| u.id, |o| o.user),
+                                   nested_loop_join(&uv, &ov, |u| u.id, |o| o.user));
+        assert_expected_eq_actual!(left_join(&uv, &ov, |u| u.id, |o| o.user),
+                                   nested_loop_left_join(&uv, &ov, |u| u.id, |o| o.user));
+    }
+
+    #[test]
+    fn left_outer_join() {
+        let u = DB::new(users());
+        let o = DB::new(orders());
+        let joined = left_join(&u.as_view(), &o.as_view(), |u| u.id, |o| o.user);
+        let expected = vec![("alice", Some(20)), ("bob", Some(10)), ("bob", Some(30)), ("carol", None)];
+        assert_expected_eq_actual!(expected,
+                                   joined.into_iter()
+                                       .map(|(u, o)| (u.name, o.map(|o| o.cents)))
+                                       .collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn cross() {
+        let u = DB::new(users());
+        let o = DB::new(orders());
+        assert_expected_eq_actual!(12, cross_join(&u.as_view(), &o.as_view()).len());
+        assert!(cross_join(&u.select_where(|_| false), &o.as_view()).is_empty());
+    }
+
+    #[test]
+    fn check_lifetimes() {
+        let u = DB::new(users());
+        let names = {
+            let o = DB::new(orders());
+            let joined = join(&u.as_view(), &o.as_view(), |u| u.id, |o| o.user);
+            joined.into_iter().map(|(u, _)| u).collect::<Vec<&User>>()
+        };
+        assert_expected_eq_actual!(vec!["alice", "bob", "bob"], names.iter().map(|u| u.name).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,195 @@
+#![cfg(feature = "jsonl")]
+
+#[macro_use]
+extern crate wk3;
+
+pub use wk3::{DB, ErrorMode, JsonlErrorKind, TypeError, Value};
+// This macro is an assertion with nicely formatted failure output
+macro_rules! assert_expected_eq_actual {
+    ($a:expr, $b:expr) => ({
+        let (a, b) = (&$a, &$b);
+        assert!(*a == *b,
+                "\nExpected `{:?}` is not equal to Actual `{:?}`\nAssertion: `assert_expected_eq_actual!({}, {})`",
+                *a,
+                *b,
+                stringify!($a),
+                stringify!($b));
+    })
+}
+
+mod jsonl {
+    use super::{DB, ErrorMode, JsonlErrorKind, TypeError, Value};
+
+    record! {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Event {
+            id: i64,
+            message: String,
+            level: Option<u8>,
+            latency: f64,
+            ok: bool,
+            payload: Vec<u8>,
+            tags: Vec<Value>,
+        }
+    }
+
+    fn event(id: i64, message: &str) -> Event {
+        Event {
+            id,
+            message: message.to_string(),
+            level: Some(3),
+            latency: 0.5,
+            ok: true,
+            payload: vec![1, 171],
+            tags: vec![Value::from("a"), Value::Int(2), Value::Null],
+        }
+    }
+
+    fn events() -> DB<Event> {
+        let mut quoted = event(2, "say \"hi\"\\\n\ttab \u{1} \u{e9} \u{1f600}");
+        quoted.level = None;
+        quoted.ok = false;
+        quoted.payload = Vec::new();
+        quoted.tags = vec![Value::List(vec![Value::Float(1.5), Value::Bool(true)])];
+        DB::new(vec![event(1, "started"), quoted, event(3, "")])
+    }
+
+    fn write(x: &DB<Event>) -> String {
+        let mut out = Vec::new();
+        x.as_view().write_jsonl(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn error(input: &str) -> (usize, JsonlErrorKind) {
+        let e = DB::<Event>::load_jsonl(input.as_bytes()).unwrap_err();
+        (e.line(), e.into_kind())
+    }
+
+    #[test]
+    fn write_format() {
+        let x = DB::new(vec![event(1, "started")]);
+        let expected = "{\"id\":1,\"message\":\"started\",\"level\":3,\"latency\":0.5,\"ok\":true,\
+                        \"payload\":\"01ab\",\"tags\":[\"a\",2,null]}\n";
+        assert_expected_eq_actual!(expected, write(&x));
+    }
+
+    #[test]
+    fn round_trip() {
+        let x = events();
+        let y: DB<Event> = DB::load_jsonl(write(&x).as_bytes()).unwrap();
+        assert_expected_eq_actual!(x, y);
+        assert_expected_eq_actual!(3, write(&x).lines().count());
+    }
+
+    #[test]
+    fn view_order_is_kept() {
+        let x = events();
+        let mut out = Vec::new();
+        x.as_view().order_by_desc(|e: &Event| e.id).limit(2).write_jsonl(&mut out).unwrap();
+        let y: DB<Event> = DB::load_jsonl(&out[..]).unwrap();
+        let ids: Vec<i64> = y.iter().map(|e| e.id).collect();
+        assert_expected_eq_actual!(vec![3, 2], ids);
+    }
+
+    #[test]
+    fn lenient_input() {
+        let input = "\n  { \"tags\" : [ ], \"ok\":false, \"latency\": 2, \"payload\":\"\",\r\n\
+                     {\"id\":-7,\"message\":\"\\u00e9\\ud83d\\ude00\\/\",\"latency\":1e-3,\
+                     \"ok\":true,\"payload\":\"FF\",\"tags\":[]}\n";
+        // The first record spans two lines, which JSON Lines doesn't allow
+        match error(input) {
+            (2, JsonlErrorKind::Syntax { .. }) => {}
+            other => panic!("unexpected error {:?}", other),
+        }
+        let input = &input[input.find("\r\n").unwrap() + 2..];
+        let x: DB<Event> = DB::load_jsonl(input.as_bytes()).unwrap();
+        let e = x.iter().next().unwrap();
+        assert_expected_eq_actual!((-7, "\u{e9}\u{1f600}/", None, 0.001, vec![255]),
+                                   (e.id, &e.message[..], e.level, e.latency, e.payload.clone()));
+    }
+
+    #[test]
+    fn syntax_errors() {
+        for input in &["{\"id\":1", "{\"id\":01}", "{\"id\":1,}", "{\"id\":\"a}", "{\"id\":[1 2]}",
+                       "{\"id\":tru}", "{\"id\":1} x", "{\"id\":\"\\q\"}", "{\"id\":\"\\u+123\"}",
+                       "{\"id\":\"\\udc00\"}"] {
+            match error(input) {
+                (1, JsonlErrorKind::Syntax { .. }) => {}
+                other => panic!("unexpected error {:?} for {}", other, input),
+            }
+        }
+    }
+
+    #[test]
+    fn record_errors() {
+        let good = write(&DB::new(vec![event(1, "ok")]));
+        let input = format!("{}[1, 2]\n", good);
+        match error(&input) {
+            (2, JsonlErrorKind::NotAnObject) => {}
+            other => panic!("unexpected error {:?}", other),
+        }
+        match error(&good.replace("\"id\":1", "\"id\":{\"n\":1}")) {
+            (1, JsonlErrorKind::ObjectValue { ref field }) => assert_eq!("id", field),
+            other => panic!("unexpected error {:?}", other),
+        }
+        match error(&good.replace("\"id\":1", "\"id\":1,\"id\":2")) {
+            (1, JsonlErrorKind::DuplicateField(ref name)) => assert_eq!("id", name),
+            other => panic!("unexpected error {:?}", other),
+        }
+        match error(&good.replace("\"01ab\"", "\"xyz\"")) {
+            (1, JsonlErrorKind::BadBytes { ref field }) => assert_eq!("payload", field),
+            other => panic!("unexpected error {:?}", other),
+        }
+        match error(&good.replace("\"level\":3", "\"level\":300")) {
+            (1, JsonlErrorKind::Type(TypeError::OutOfRange { .. })) => {}
+            other => panic!("unexpected error {:?}", other),
+        }
+        match error(&good.replace("\"id\":1,", "")) {
+            (1, JsonlErrorKind::Type(TypeError::WrongType { found: None, .. })) => {}
+            other => panic!("unexpected error {:?}", other),
+        }
+        match error(&good.replace("\"ok\":true", "\"ok\":\"yes\"")) {
+            (1, JsonlErrorKind::Type(TypeError::WrongType { .. })) => {}
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+
+    #[test]
+    fn other_members_are_skipped() {
+        let good = write(&DB::new(vec![event(1, "ok")]));
+        let extra = "\"ok\":true,\"host\":\"a\",\
+                     \"request\":{\"path\":\"/\",\"headers\":{\"n\":[{}]}}";
+        let input = good.replace("\"ok\":true", extra);
+        let x: DB<Event> = DB::load_jsonl(input.as_bytes()).unwrap();
+        assert_expected_eq_actual!(DB::new(vec![event(1, "ok")]), x);
+    }
+
+    #[test]
+    fn skip_mode() {
+        let good = write(&events());
+        let lines: Vec<&str> = good.lines().collect();
+        let input = format!("{}\nnot json\n{}\n{{\"id\":1}}\n{}\n", lines[0], lines[1], lines[2]);
+        let (x, skipped) = DB::<Event>::load_jsonl_with(input.as_bytes(), ErrorMode::Skip).unwrap();
+        assert_expected_eq_actual!(events(), x);
+        let lines: Vec<usize> = skipped.iter().map(|e| e.line()).collect();
+        assert_expected_eq_actual!(vec![2, 4], lines);
+
+        let strict = DB::<Event>::load_jsonl_with(input.as_bytes(), ErrorMode::Strict);
+        assert_expected_eq_actual!(Some(2), strict.err().map(|e| e.line()));
+    }
+
+    #[test]
+    fn non_finite_floats() {
+        let mut x = DB::new(vec![event(1, "nan")]);
+        x.iter_mut().next().unwrap().latency = f64::NAN;
+        assert!(write(&x).contains("\"latency\":null"));
+    }
+
+    #[test]
+    fn error_messages() {
+        let e = DB::<Event>::load_jsonl(&b"\n{\"id\":x}"[..]).unwrap_err();
+        assert_expected_eq_actual!("expected value at line 2, column 7", e.to_string());
+        let e = DB::<Event>::load_jsonl(&b"{\"id\":1,\"id\":1}"[..]).unwrap_err();
+        assert_expected_eq_actual!("duplicate field `id` at line 1", e.to_string());
+    }
+}
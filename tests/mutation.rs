@@ -0,0 +1,69 @@
+extern crate wk3;
+
+pub use wk3::DB;
+
+// This macro is an assertion with nicely formatted failure output
+macro_rules! assert_expected_eq_actual {
+    ($a:expr, $b:expr) => ({
+        let (a, b) = (&$a, &$b);
+        assert!(*a == *b,
+                "\nExpected `{:?}` is not equal to Actual `{:?}`\nAssertion: `assert_expected_eq_actual!({}, {})`",
+                *a,
+                *b,
+                stringify!($a),
+                stringify!($b));
+    })
+}
+
+mod mutation {
+    use super::DB;
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    struct NoCopy(i32);
+
+    fn is_positive(i: &NoCopy) -> bool { i.0 > 0 }
+
+    fn is_even(i: &NoCopy) -> bool { i.0 % 2 == 0 }
+
+    #[test]
+    fn insert_and_extend() {
+        let mut x = DB::new(vec![NoCopy(1)]);
+        x.insert(NoCopy(2));
+        x.extend((3..5).map(NoCopy));
+        assert_expected_eq_actual!(4, x.len());
+        assert_expected_eq_actual!((1..5).map(NoCopy).collect::<Vec<_>>(),
+                                   x.into_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn insert_after_view() {
+        let mut x = DB::new((0..4).map(NoCopy).collect());
+        let before = x.select_where(is_even).len();
+        x.insert(NoCopy(4));
+        assert_expected_eq_actual!(before + 1, x.select_where(is_even).len());
+    }
+
+    #[test]
+    fn remove_where() {
+        let mut x = DB::new((-5..5).map(NoCopy).collect());
+        let removed = x.remove_where(is_positive);
+        assert_expected_eq_actual!((1..5).map(NoCopy).collect::<Vec<_>>(), removed);
+        assert_expected_eq_actual!((-5..1).map(NoCopy).collect::<Vec<_>>(),
+                                   x.into_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn remove_where_nothing() {
+        let mut x = DB::new((0..3).map(NoCopy).collect());
+        assert_expected_eq_actual!(Vec::<NoCopy>::new(), x.remove_where(|i| i.0 > 10));
+        assert_expected_eq_actual!(3, x.len());
+    }
+
+    #[test]
+    fn retain() {
+        let mut x = DB::new((0..10).map(NoCopy).collect());
+        x.retain(is_even);
+        assert_expected_eq_actual!(vec![NoCopy(0), NoCopy(2), NoCopy(4), NoCopy(6), NoCopy(8)],
+                                   x.into_iter().collect::<Vec<_>>());
+    }
+}
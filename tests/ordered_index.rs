@@ -0,0 +1,89 @@
+extern crate wk3;
+
+pub use wk3::DB;
+
+// This macro is an assertion with nicely formatted failure output
+macro_rules! assert_expected_eq_actual {
+    ($a:expr, $b:expr) => ({
+        let (a, b) = (&$a, &$b);
+        assert!(*a == *b,
+                "\nExpected `{:?}` is not equal to Actual `{:?}`\nAssertion: `assert_expected_eq_actual!({}, {})`",
+                *a,
+                *b,
+                stringify!($a),
+                stringify!($b));
+    })
+}
+
+mod ordered_index {
+    use super::DB;
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    struct Event {
+        name: &'static str,
+        at: u64,
+    }
+
+    fn db() -> DB<Event> {
+        let mut x = DB::new(vec![Event { name: "c", at: 30 },
+                                 Event { name: "a", at: 10 },
+                                 Event { name: "d", at: 40 },
+                                 Event { name: "b", at: 20 },
+                                 Event { name: "b2", at: 20 }]);
+        x.create_ordered_index("at", |e: &Event| e.at);
+        x
+    }
+
+    fn names(view: wk3::DBView<Event>) -> Vec<&'static str> {
+        view.into_iter().map(|e| e.name).collect()
+    }
+
+    #[test]
+    fn ranges() {
+        let x = db();
+        assert_expected_eq_actual!(vec!["b", "b2", "c"], names(x.select_range("at", 20u64..40)));
+        assert_expected_eq_actual!(vec!["b", "b2", "c", "d"], names(x.select_range("at", 15u64..)));
+        assert_expected_eq_actual!(vec!["a"], names(x.select_range("at", ..=10u64)));
+        let (hi, lo) = (40u64, 20u64);
+        assert!(x.select_range("at", hi..lo).is_empty());
+        assert!(x.select_range("at", 20u64..20).is_empty());
+    }
+
+    #[test]
+    fn ordered_first_and_last() {
+        let x = db();
+        assert_expected_eq_actual!(vec!["a", "b", "b2", "c", "d"], names(x.select_ordered::<u64>("at")));
+        assert_expected_eq_actual!(vec!["a", "b"], names(x.select_first::<u64>("at", 2)));
+        assert_expected_eq_actual!(vec!["b2", "c", "d"], names(x.select_last::<u64>("at", 3)));
+        assert_expected_eq_actual!(5, x.select_last::<u64>("at", 10).len());
+    }
+
+    #[test]
+    fn insert_and_remove() {
+        let mut x = db();
+        x.insert(Event { name: "e", at: 5 });
+        x.remove_where(|e| e.name == "c");
+        assert_expected_eq_actual!(vec!["e", "a", "b", "b2", "d"], names(x.select_ordered::<u64>("at")));
+        assert_expected_eq_actual!(vec!["b", "b2", "d"], names(x.select_range("at", 20u64..50)));
+    }
+
+    #[test]
+    fn edits_through_view_mut() {
+        let mut x = db();
+        for e in x.select_where_mut(|e| e.name == "a" || e.name == "d") {
+            e.at += 100;
+        }
+        assert_expected_eq_actual!(vec!["b", "b2", "c", "a", "d"], names(x.select_ordered::<u64>("at")));
+        assert_expected_eq_actual!(vec!["b", "b2"], names(x.select_first::<u64>("at", 2)));
+        assert_expected_eq_actual!(vec!["a", "d"], names(x.select_last::<u64>("at", 2)));
+        assert_expected_eq_actual!(vec!["a"], names(x.select_range("at", 100u64..120)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn hash_index_is_not_ordered() {
+        let mut x = db();
+        x.create_index("name", |e: &Event| e.name);
+        x.select_ordered::<&'static str>("name");
+    }
+}
@@ -0,0 +1,79 @@
+extern crate wk3;
+
+pub use wk3::{DB, SortKey};
+
+// This macro is an assertion with nicely formatted failure output
+macro_rules! assert_expected_eq_actual {
+    ($a:expr, $b:expr) => ({
+        let (a, b) = (&$a, &$b);
+        assert!(*a == *b,
+                "\nExpected `{:?}` is not equal to Actual `{:?}`\nAssertion: `assert_expected_eq_actual!({}, {})`",
+                *a,
+                *b,
+                stringify!($a),
+                stringify!($b));
+    })
+}
+
+mod ordering {
+    use super::{DB, SortKey};
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    struct Person {
+        name: &'static str,
+        age: u32,
+    }
+
+    fn people() -> Vec<Person> {
+        vec![Person { name: "carol", age: 30 },
+             Person { name: "alice", age: 25 },
+             Person { name: "bob", age: 30 },
+             Person { name: "dave", age: 20 }]
+    }
+
+    fn names(view: wk3::DBView<Person>) -> Vec<&'static str> {
+        view.into_iter().map(|p| p.name).collect()
+    }
+
+    #[test]
+    fn order_by_is_stable() {
+        let x = DB::new(people());
+        assert_expected_eq_actual!(vec!["dave", "alice", "carol", "bob"],
+                                   names(x.as_view().order_by(|p| p.age)));
+    }
+
+    #[test]
+    fn order_by_desc_is_stable() {
+        let x = DB::new(people());
+        assert_expected_eq_actual!(vec!["carol", "bob", "alice", "dave"],
+                                   names(x.as_view().order_by_desc(|p| p.age)));
+    }
+
+    #[test]
+    fn order_by_keys() {
+        let x = DB::new(people());
+        let keys = [SortKey::desc(|p: &Person| p.age), SortKey::asc(|p: &Person| p.name)];
+        assert_expected_eq_actual!(vec!["bob", "carol", "alice", "dave"],
+                                   names(x.as_view().order_by_keys(&keys)));
+    }
+
+    #[test]
+    fn paging() {
+        let x = DB::new((0..25).collect::<Vec<i32>>());
+        let sorted = x.as_view().order_by_desc(|&i| i);
+        let page = |n: usize| sorted.offset(n * 10).limit(10).into_iter().cloned().collect::<Vec<_>>();
+        assert_expected_eq_actual!((15..25).rev().collect::<Vec<_>>(), page(0));
+        assert_expected_eq_actual!((5..15).rev().collect::<Vec<_>>(), page(1));
+        assert_expected_eq_actual!((0..5).rev().collect::<Vec<_>>(), page(2));
+        assert!(sorted.offset(100).is_empty());
+    }
+
+    #[test]
+    fn ordering_keeps_row_ids() {
+        let x = DB::new(people());
+        let view = x.as_view().order_by(|p| p.name);
+        for (id, p) in view.rows() {
+            assert_expected_eq_actual!(Some(p), x.get(id));
+        }
+    }
+}
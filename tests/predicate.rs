@@ -0,0 +1,101 @@
+extern crate wk3;
+
+pub use wk3::{DB, Predicate, filter_one, filter_two};
+
+// This macro is an assertion with nicely formatted failure output
+macro_rules! assert_expected_eq_actual {
+    ($a:expr, $b:expr) => ({
+        let (a, b) = (&$a, &$b);
+        assert!(*a == *b,
+                "\nExpected `{:?}` is not equal to Actual `{:?}`\nAssertion: `assert_expected_eq_actual!({}, {})`",
+                *a,
+                *b,
+                stringify!($a),
+                stringify!($b));
+    })
+}
+
+mod predicate {
+    use super::{DB, Predicate, filter_one, filter_two};
+    use wk3::predicate::{all_of, any_of, named, not};
+
+    fn is_positive(i: &i32) -> bool { *i > 0 }
+
+    fn is_even(i: &i32) -> bool { i % 2 == 0 }
+
+    fn values(view: wk3::DBView<i32>) -> Vec<i32> {
+        view.into_iter().cloned().collect()
+    }
+
+    #[test]
+    fn combinators() {
+        let x = DB::new((-4..5).collect());
+        assert_expected_eq_actual!(vec![2, 4], values(x.select_where(is_positive.and(is_even).as_fn())));
+        assert_expected_eq_actual!(vec![-4, -2, 0, 1, 2, 3, 4],
+                                   values(x.select_where(is_positive.or(is_even).as_fn())));
+        assert_expected_eq_actual!(vec![-3, -1, 1, 3], values(x.select_where(not(is_even).as_fn())));
+        assert_expected_eq_actual!(vec![-3, -1], values(x.select_where(is_even.or(is_positive).not().as_fn())));
+    }
+
+    #[test]
+    fn lists() {
+        let x = DB::new((-4..5).collect());
+        let small = named("small", |i: &i32| i.abs() < 2);
+        let both = all_of(vec![is_positive.shared(), is_even.shared()]);
+        assert_expected_eq_actual!(vec![2, 4], values(x.select_where(both.as_fn())));
+        let either = any_of(vec![small.shared(), is_even.and(is_positive).shared()]);
+        assert_expected_eq_actual!(vec![-1, 0, 1, 2, 4], values(x.select_where(either.as_fn())));
+        assert_expected_eq_actual!(9, x.select_where(all_of(Vec::new()).as_fn()).len());
+        assert!(x.select_where(any_of(Vec::new()).as_fn()).is_empty());
+    }
+
+    #[test]
+    fn descriptions() {
+        let positive = named("x > 0", is_positive);
+        let even = named("x % 2 == 0", is_even);
+        assert_expected_eq_actual!("(x > 0 AND NOT x % 2 == 0)",
+                                   format!("{:?}", positive.clone().and(even.clone().not())));
+        assert_expected_eq_actual!("ANY OF [x > 0, (x > 0 OR x % 2 == 0)]",
+                                   format!("{:?}", any_of(vec![positive.clone().shared(),
+                                                               positive.or(even).shared()])));
+        assert!(format!("{:?}", not(is_even)).ends_with("is_even"));
+    }
+
+    #[test]
+    fn selecting_with_predicates() {
+        let positive = named("x > 0", is_positive);
+        let positive_even = positive.by_ref().and(is_even);
+        let mut a = DB::new((-4..5).collect());
+        let b = DB::new((0..3).collect());
+        {
+            let (view_a, view_b) = (a.as_view(), b.as_view());
+            assert_expected_eq_actual!(vec![2, 4], values(filter_one(&view_a, positive_even.as_fn())));
+            let (pos_a, pos_b) = filter_two(&view_a, &view_b, positive.as_fn());
+            assert_expected_eq_actual!((4, 2), (pos_a.len(), pos_b.len()));
+            assert_expected_eq_actual!(2, view_a.select_where(positive_even.as_fn()).len());
+            assert_expected_eq_actual!(4, view_a.count_where(positive.as_fn()));
+        }
+        for i in a.select_where_mut(positive.as_fn()).select_where_mut(is_even) {
+            *i = 0;
+        }
+        assert_expected_eq_actual!(vec![1, 3], a.remove_where(positive.as_fn()));
+        a.retain(not(is_even).as_fn());
+        assert_expected_eq_actual!(vec![-3, -1], values(a.as_view()));
+    }
+
+    #[test]
+    fn shared_across_threads() {
+        let predicate = is_positive.and(named("odd", |i: &i32| i % 2 != 0)).shared();
+        let handles: Vec<_> = (0..2)
+            .map(|n| {
+                let predicate = predicate.clone();
+                ::std::thread::spawn(move || {
+                    let x = DB::new((n * 10..n * 10 + 10).collect());
+                    x.count_where(predicate.as_fn())
+                })
+            })
+            .collect();
+        let counts: Vec<usize> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_expected_eq_actual!(vec![5, 5], counts);
+    }
+}
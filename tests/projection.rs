@@ -0,0 +1,99 @@
+extern crate wk3;
+
+pub use wk3::{DB, filter_one, filter_two};
+
+// This macro is an assertion with nicely formatted failure output
+macro_rules! assert_expected_eq_actual {
+    ($a:expr, $b:expr) => ({
+        let (a, b) = (&$a, &$b);
+        assert!(*a == *b,
+                "\nExpected `{:?}` is not equal to Actual `{:?}`\nAssertion: `assert_expected_eq_actual!({}, {})`",
+                *a,
+                *b,
+                stringify!($a),
+                stringify!($b));
+    })
+}
+
+mod projection {
+    use super::{DB, filter_one, filter_two};
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct NoCopy(i32);
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct Person {
+        name: String,
+        age: NoCopy,
+    }
+
+    fn person(name: &str, age: i32) -> Person {
+        Person { name: name.to_string(), age: NoCopy(age) }
+    }
+
+    fn db() -> DB<Person> {
+        DB::new(vec![person("alice", 30), person("bob", 25), person("carol", 41)])
+    }
+
+    #[test]
+    fn project() {
+        let x = db();
+        let ages = x.as_view().project(|p| &p.age);
+        assert_expected_eq_actual!(vec![&NoCopy(30), &NoCopy(25), &NoCopy(41)],
+                                   ages.iter().collect::<Vec<_>>());
+        let ids: Vec<_> = ages.rows().map(|(id, _)| id).collect();
+        let parent_ids: Vec<_> = x.as_view().rows().map(|(id, _)| id).collect();
+        assert_expected_eq_actual!(parent_ids, ids);
+    }
+
+    #[test]
+    fn outlives_parent_view() {
+        let x = db();
+        let names = {
+            let view = x.select_where(|p| p.age.0 > 26);
+            view.project(|p| &p.name)
+        };
+        let names: Vec<&str> = names.iter().map(|s| &s[..]).collect();
+        assert_expected_eq_actual!(vec!["alice", "carol"], names);
+    }
+
+    #[test]
+    fn filter_and_select_projections() {
+        let x = db();
+        let y = DB::new(vec![person("dave", 19)]);
+        let ages = x.as_view().project(|p| &p.age);
+        let old = filter_one(&ages, |a| a.0 > 26);
+        assert_expected_eq_actual!(vec![&NoCopy(30), &NoCopy(41)], old.iter().collect::<Vec<_>>());
+        let (a, b) = filter_two(&ages, &y.as_view().project(|p| &p.age), |a: &NoCopy| a.0 < 26);
+        assert_expected_eq_actual!(vec![&NoCopy(25)], a.iter().collect::<Vec<_>>());
+        assert_expected_eq_actual!(vec![&NoCopy(19)], b.iter().collect::<Vec<_>>());
+        let nested = old.select_where(|a| a.0 < 40).project(|a| &a.0);
+        assert_expected_eq_actual!(vec![&30], nested.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn project_mut() {
+        let mut x = db();
+        {
+            let view = x.select_where_mut(|p| p.age.0 < 40).project_mut(|p| &mut p.age);
+            for age in view {
+                age.0 += 1;
+            }
+        }
+        let ages: Vec<i32> = x.iter().map(|p| p.age.0).collect();
+        assert_expected_eq_actual!(vec![31, 26, 41], ages);
+    }
+
+    #[test]
+    fn project_mut_keeps_indexes_in_sync() {
+        let mut x = db();
+        x.create_index("age", |p: &Person| p.age.0);
+        {
+            let mut view = x.as_view_mut().project_mut(|p| &mut p.age);
+            for age in view.iter_mut() {
+                age.0 = 30;
+            }
+        }
+        assert_expected_eq_actual!(3, x.select_eq("age", &30).len());
+    }
+}
@@ -0,0 +1,140 @@
+extern crate wk3;
+
+pub use wk3::DB;
+
+// This macro is an assertion with nicely formatted failure output
+macro_rules! assert_expected_eq_actual {
+    ($a:expr, $b:expr) => ({
+        let (a, b) = (&$a, &$b);
+        assert!(*a == *b,
+                "\nExpected `{:?}` is not equal to Actual `{:?}`\nAssertion: `assert_expected_eq_actual!({}, {})`",
+                *a,
+                *b,
+                stringify!($a),
+                stringify!($b));
+    })
+}
+
+mod query {
+    use super::DB;
+    use std::cell::Cell;
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    struct Person {
+        name: String,
+        age: u32,
+    }
+
+    fn person(name: &str, age: u32) -> Person {
+        Person { name: name.to_string(), age }
+    }
+
+    fn db() -> DB<Person> {
+        DB::new(vec![person("alice", 30),
+                     person("bob", 25),
+                     person("carol", 41),
+                     person("dave", 30),
+                     person("erin", 19)])
+    }
+
+    fn names<'a>(view: wk3::DBView<'a, Person>) -> Vec<&'a str> {
+        view.into_iter().map(|p| &p.name[..]).collect()
+    }
+
+    #[test]
+    fn filters_are_combined() {
+        let x = db();
+        let view = x.query()
+            .filter(|p| p.age >= 25)
+            .filter(|p| p.name != "alice")
+            .collect_view();
+        assert_expected_eq_actual!(vec!["bob", "carol", "dave"], names(view));
+    }
+
+    #[test]
+    fn matches_chained_select_where() {
+        let x = db();
+        let chained = x.select_where(|p| p.age < 40).order_by(|p| p.age).limit(2);
+        let query = x.query().filter(|p| p.age < 40).order_by(|p| p.age).limit(2);
+        assert_expected_eq_actual!(chained, query.collect_view());
+    }
+
+    #[test]
+    fn order_offset_limit() {
+        let x = db();
+        let query = x.query().order_by_desc(|p| p.age).order_by(|p| p.name.clone());
+        let view = query.offset(1).limit(2).collect_view();
+        assert_expected_eq_actual!(vec!["alice", "dave"], names(view));
+        assert!(x.query().offset(10).collect_view().is_empty());
+    }
+
+    #[test]
+    fn stops_at_limit() {
+        let x = db();
+        let tested = Cell::new(0);
+        let view = x.query()
+            .filter(|_| {
+                tested.set(tested.get() + 1);
+                true
+            })
+            .limit(2)
+            .collect_view();
+        assert_expected_eq_actual!(vec!["alice", "bob"], names(view));
+        assert_expected_eq_actual!(2, tested.get());
+    }
+
+    #[test]
+    fn uses_index() {
+        let mut x = db();
+        x.create_index("age", |p: &Person| p.age);
+        let tested = Cell::new(0);
+        let view = x.query()
+            .filter(|p| {
+                tested.set(tested.get() + 1);
+                p.name != "alice"
+            })
+            .filter_eq("age", 30u32)
+            .collect_view();
+        assert_expected_eq_actual!(vec!["dave"], names(view));
+        assert_expected_eq_actual!(2, tested.get());
+    }
+
+    #[test]
+    fn picks_most_selective_index() {
+        let mut x = db();
+        x.create_index("age", |p: &Person| p.age);
+        x.create_ordered_index("by_age", |p: &Person| p.age);
+        let view = x.query().filter_range("by_age", 20u32..).filter_eq("age", 30u32).collect_view();
+        assert_expected_eq_actual!(vec!["alice", "dave"], names(view));
+        let view = x.query().filter_range("by_age", 30u32..35).collect_view();
+        assert_expected_eq_actual!(vec!["alice", "dave"], names(view));
+    }
+
+    #[test]
+    fn index_sees_edits() {
+        let mut x = db();
+        x.create_ordered_index("age", |p: &Person| p.age);
+        for p in x.iter_mut() {
+            p.age += 1;
+        }
+        let view = x.query().filter_range("age", ..=26u32).collect_view();
+        assert_expected_eq_actual!(vec!["bob", "erin"], names(view));
+    }
+
+    #[test]
+    fn debug_lists_steps() {
+        let mut x = db();
+        x.create_index("age", |p: &Person| p.age);
+        let query = x.query().filter_eq("age", 30u32).limit(1);
+        let printed = format!("{:?}", query);
+        assert!(printed.contains("age = 30"), "{}", printed);
+        assert!(printed.contains("limit: Some(1)"), "{}", printed);
+    }
+
+    #[test]
+    #[should_panic]
+    fn missing_index_panics() {
+        let x = db();
+        x.query().filter_eq("age", 30u32);
+    }
+}
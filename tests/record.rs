@@ -0,0 +1,80 @@
+#[macro_use]
+extern crate wk3;
+
+pub use wk3::{DB, Field, FieldAccess, FieldType, Record, Schema, Value};
+
+// This macro is an assertion with nicely formatted failure output
+macro_rules! assert_expected_eq_actual {
+    ($a:expr, $b:expr) => ({
+        let (a, b) = (&$a, &$b);
+        assert!(*a == *b,
+                "\nExpected `{:?}` is not equal to Actual `{:?}`\nAssertion: `assert_expected_eq_actual!({}, {})`",
+                *a,
+                *b,
+                stringify!($a),
+                stringify!($b));
+    })
+}
+
+mod record {
+    use super::{DB, Field, FieldAccess, FieldType, Record, Schema, Value};
+
+    record! {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Person {
+            name: String,
+            age: u32,
+            /// Not everyone has been scored yet
+            score: Option<f64>,
+            active: bool,
+        }
+    }
+
+    fn person(name: &str, age: u32, score: Option<f64>) -> Person {
+        Person { name: name.to_string(), age, score, active: true }
+    }
+
+    #[test]
+    fn schema() {
+        let field = |name: &str, ty, nullable| Field { name: name.to_string(), ty, nullable };
+        assert_expected_eq_actual!(Schema::new(vec![field("name", FieldType::Str, false),
+                                                     field("age", FieldType::Int, false),
+                                                     field("score", FieldType::Float, true),
+                                                     field("active", FieldType::Bool, false)]),
+                                   Person::schema());
+        let schema = Person::schema();
+        assert_expected_eq_actual!(Some(2), schema.position("score"));
+        assert_expected_eq_actual!(None, schema.field("height"));
+        assert_expected_eq_actual!(4, schema.len());
+    }
+
+    #[test]
+    fn get() {
+        let p = person("alice", 30, None);
+        assert_expected_eq_actual!(Some(Value::Str("alice".to_string())), p.get("name"));
+        assert_expected_eq_actual!(Some(Value::Int(30)), p.get("age"));
+        assert_expected_eq_actual!(Some(Value::Null), p.get("score"));
+        assert_expected_eq_actual!(None, p.get("height"));
+        assert_expected_eq_actual!(vec![Value::from("alice"),
+                                        Value::Int(30),
+                                        Value::Null,
+                                        Value::Bool(true)],
+                                   p.values());
+    }
+
+    #[test]
+    fn records_work_with_sql() {
+        let x = DB::new(vec![person("alice", 30, Some(1.5)),
+                             person("bob", 25, None),
+                             person("carol", 41, Some(3.0))]);
+        let view = x.sql("SELECT * WHERE score IS NOT NULL ORDER BY age DESC").unwrap();
+        let names: Vec<&str> = view.into_iter().map(|p| &p.name[..]).collect();
+        assert_expected_eq_actual!(vec!["carol", "alice"], names);
+    }
+
+    #[test]
+    #[should_panic]
+    fn duplicate_fields_panic() {
+        Schema::new(vec![Field::of::<i64>("a"), Field::of::<String>("a")]);
+    }
+}
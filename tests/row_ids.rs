@@ -0,0 +1,86 @@
+extern crate wk3;
+
+pub use wk3::{DB, RowId};
+
+// This macro is an assertion with nicely formatted failure output
+macro_rules! assert_expected_eq_actual {
+    ($a:expr, $b:expr) => ({
+        let (a, b) = (&$a, &$b);
+        assert!(*a == *b,
+                "\nExpected `{:?}` is not equal to Actual `{:?}`\nAssertion: `assert_expected_eq_actual!({}, {})`",
+                *a,
+                *b,
+                stringify!($a),
+                stringify!($b));
+    })
+}
+
+mod row_ids {
+    use super::{DB, RowId};
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    struct NoCopy(i32);
+
+    fn is_even(i: &NoCopy) -> bool { i.0 % 2 == 0 }
+
+    #[test]
+    fn lookup_after_view_dropped() {
+        let mut x = DB::new((0..10).map(NoCopy).collect());
+        let ids: Vec<RowId> = x.select_where(is_even).rows().map(|(id, _)| id).collect();
+        assert_expected_eq_actual!(5, ids.len());
+        for &id in &ids {
+            *x.get_mut(id).unwrap() = NoCopy(-1);
+        }
+        assert_expected_eq_actual!(Some(&NoCopy(-1)), x.get(ids[2]));
+        assert_expected_eq_actual!(0, x.select_where(is_even).len());
+    }
+
+    #[test]
+    fn ids_are_stable_across_removal() {
+        let mut x = DB::new((0..6).map(NoCopy).collect());
+        let ids: Vec<RowId> = x.as_view().rows().map(|(id, _)| id).collect();
+        x.remove_where(is_even);
+        assert_expected_eq_actual!(None, x.get(ids[0]));
+        assert_expected_eq_actual!(Some(&NoCopy(3)), x.get(ids[3]));
+        assert_expected_eq_actual!(Some(NoCopy(5)), x.remove(ids[5]));
+        assert_expected_eq_actual!(None, x.remove(ids[5]));
+        assert!(!x.contains(ids[5]));
+    }
+
+    #[test]
+    fn ids_are_not_reused() {
+        let mut x = DB::new(vec![NoCopy(0)]);
+        let first = x.as_view().rows().next().unwrap().0;
+        x.remove(first);
+        let second = x.insert(NoCopy(1));
+        assert!(first != second);
+        assert_expected_eq_actual!(None, x.get(first));
+        assert_expected_eq_actual!(Some(&NoCopy(1)), x.get(second));
+    }
+
+    #[test]
+    fn mut_view_rows() {
+        let mut x = DB::new((0..4).map(NoCopy).collect());
+        let mut seen = Vec::new();
+        for (id, entry) in x.select_where_mut(is_even).into_rows() {
+            entry.0 *= 10;
+            seen.push(id);
+        }
+        assert_expected_eq_actual!(Some(&NoCopy(20)), x.get(seen[1]));
+        assert_expected_eq_actual!(Some(&NoCopy(1)), x.get(x.as_view().rows().nth(1).unwrap().0));
+    }
+
+    #[test]
+    fn views_compare_entries_not_ids() {
+        let mut x = DB::new(vec![NoCopy(1), NoCopy(2), NoCopy(1)]);
+        let mut y = DB::new(vec![NoCopy(1), NoCopy(1)]);
+        // The second entry of `y` has a different id to the one it matches in `x`
+        let ids = |v: &DB<NoCopy>| -> Vec<RowId> {
+            v.select_where(|i| i.0 == 1).rows().map(|(id, _)| id).collect()
+        };
+        assert!(ids(&x) != ids(&y));
+        assert_expected_eq_actual!(x.select_where(|i| i.0 == 1), y.as_view());
+        assert!(x.as_view() != y.as_view());
+        assert!(x.select_where_mut(|i| i.0 == 1) == y.as_view_mut());
+    }
+}
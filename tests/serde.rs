@@ -0,0 +1,128 @@
+#![cfg(feature = "serde")]
+
+#[macro_use]
+extern crate serde;
+extern crate ron;
+extern crate serde_json;
+extern crate wk3;
+
+pub use wk3::{DB, RowId};
+// This macro is an assertion with nicely formatted failure output
+macro_rules! assert_expected_eq_actual {
+    ($a:expr, $b:expr) => ({
+        let (a, b) = (&$a, &$b);
+        assert!(*a == *b,
+                "\nExpected `{:?}` is not equal to Actual `{:?}`\nAssertion: `assert_expected_eq_actual!({}, {})`",
+                *a,
+                *b,
+                stringify!($a),
+                stringify!($b));
+    })
+}
+
+mod serde_support {
+    use super::{DB, RowId};
+    use serde_json;
+
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    struct NoCopy(i32);
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    struct Person {
+        name: String,
+        age: u32,
+        height: f64,
+    }
+
+    fn round_trip<T>(x: &DB<T>) -> DB<T>
+        where T: serde::Serialize + serde::de::DeserializeOwned
+    {
+        serde_json::from_str(&serde_json::to_string(x).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn empty() {
+        let x: DB<NoCopy> = DB::new(Vec::new());
+        assert_expected_eq_actual!(x, round_trip(&x));
+    }
+
+    #[test]
+    fn no_copy() {
+        let x = DB::new((-50..50).map(NoCopy).collect());
+        assert_expected_eq_actual!(x, round_trip(&x));
+    }
+
+    #[test]
+    fn structs() {
+        let x = DB::new(vec![
+            Person { name: "Ann".to_string(), age: 30, height: 1.7 },
+            Person { name: "Bob".to_string(), age: 25, height: 1.8 },
+        ]);
+        assert_expected_eq_actual!(x, round_trip(&x));
+    }
+
+    #[test]
+    fn ids_are_kept() {
+        let mut x = DB::new((0..5).map(NoCopy).collect());
+        x.remove_where(|n| n.0 % 2 == 0);
+        let mut y = round_trip(&x);
+        assert_expected_eq_actual!(x, y);
+
+        // Ids aren't reused after loading either
+        let new_x = x.insert(NoCopy(5));
+        let new_y = y.insert(NoCopy(5));
+        assert_expected_eq_actual!(new_x, new_y);
+        assert_expected_eq_actual!(x, y);
+    }
+
+    #[test]
+    fn indexes_are_not_kept() {
+        let mut x = DB::new((0..5).map(NoCopy).collect());
+        x.create_index("value", |n: &NoCopy| n.0);
+        let mut y = round_trip(&x);
+        assert!(!y.drop_index("value"));
+    }
+
+    #[test]
+    fn format() {
+        let mut x = DB::new(vec![NoCopy(1), NoCopy(2)]);
+        let first = x.as_view().rows().next().unwrap().0;
+        x.remove(first);
+        let json = serde_json::to_string(&x).unwrap();
+        assert_expected_eq_actual!(r#"{"next_id":2,"rows":[[1,2]]}"#, json);
+    }
+
+    #[test]
+    fn bad_ids() {
+        let unordered = r#"{"next_id":5,"rows":[[2,0],[1,1]]}"#;
+        assert!(serde_json::from_str::<DB<NoCopy>>(unordered).is_err());
+        let too_big = r#"{"next_id":2,"rows":[[2,0]]}"#;
+        assert!(serde_json::from_str::<DB<NoCopy>>(too_big).is_err());
+        let missing = r#"{"rows":[]}"#;
+        assert!(serde_json::from_str::<DB<NoCopy>>(missing).is_err());
+    }
+
+    #[test]
+    fn row_ids() {
+        let x = DB::new(vec![NoCopy(7)]);
+        let id = x.as_view().rows().next().unwrap().0;
+        let json = serde_json::to_string(&id).unwrap();
+        assert_expected_eq_actual!(id, serde_json::from_str::<RowId>(&json).unwrap());
+        // RON writes newtypes out, unlike JSON
+        let ron = ron::to_string(&id).unwrap();
+        assert_expected_eq_actual!("(0)", ron);
+        assert_expected_eq_actual!(id, ron::from_str::<RowId>(&ron).unwrap());
+        let ron = ron::to_string(&x).unwrap();
+        assert_expected_eq_actual!(x, ron::from_str::<DB<NoCopy>>(&ron).unwrap());
+    }
+
+    #[test]
+    fn view_snapshot() {
+        let x = DB::new((0..10).map(NoCopy).collect());
+        let view = x.select_where(|n| n.0 % 3 == 0).order_by_desc(|n: &NoCopy| n.0);
+        let json = serde_json::to_string(&view).unwrap();
+        assert_expected_eq_actual!("[9,6,3,0]", json);
+        let owned: Vec<NoCopy> = serde_json::from_str(&json).unwrap();
+        assert_expected_eq_actual!(vec![NoCopy(9), NoCopy(6), NoCopy(3), NoCopy(0)], owned);
+    }
+}
@@ -0,0 +1,77 @@
+extern crate wk3;
+
+pub use wk3::DB;
+
+// This macro is an assertion with nicely formatted failure output
+macro_rules! assert_expected_eq_actual {
+    ($a:expr, $b:expr) => ({
+        let (a, b) = (&$a, &$b);
+        assert!(*a == *b,
+                "\nExpected `{:?}` is not equal to Actual `{:?}`\nAssertion: `assert_expected_eq_actual!({}, {})`",
+                *a,
+                *b,
+                stringify!($a),
+                stringify!($b));
+    })
+}
+
+mod set_ops {
+    use super::DB;
+
+    fn values(view: wk3::DBView<i32>) -> Vec<i32> {
+        view.into_iter().cloned().collect()
+    }
+
+    fn is_even(i: &i32) -> bool { i % 2 == 0 }
+
+    fn is_small(i: &i32) -> bool { *i < 4 }
+
+    #[test]
+    fn union() {
+        let x = DB::new((0..8).collect());
+        let (evens, small) = (x.select_where(is_even), x.select_where(is_small));
+        assert_expected_eq_actual!(vec![0, 2, 4, 6, 1, 3], values(evens.union(&small)));
+    }
+
+    #[test]
+    fn intersect() {
+        let x = DB::new((0..8).collect());
+        let (evens, small) = (x.select_where(is_even), x.select_where(is_small));
+        assert_expected_eq_actual!(vec![0, 2], values(evens.intersect(&small)));
+        assert_expected_eq_actual!(evens.intersect(&small).len(), small.intersect(&evens).len());
+    }
+
+    #[test]
+    fn difference() {
+        let x = DB::new((0..8).collect());
+        let (evens, small) = (x.select_where(is_even), x.select_where(is_small));
+        assert_expected_eq_actual!(vec![4, 6], values(evens.difference(&small)));
+        assert_expected_eq_actual!(vec![1, 3], values(small.difference(&evens)));
+    }
+
+    #[test]
+    fn symmetric_difference() {
+        let x = DB::new((0..8).collect());
+        let (evens, small) = (x.select_where(is_even), x.select_where(is_small));
+        assert_expected_eq_actual!(vec![4, 6, 1, 3], values(evens.symmetric_difference(&small)));
+    }
+
+    #[test]
+    fn duplicate_values_are_distinct_entries() {
+        let x = DB::new(vec![7, 7, 7, 1]);
+        let first_two = x.as_view().limit(2);
+        let last_two = x.as_view().offset(2);
+        assert_expected_eq_actual!(vec![7, 7, 7, 1], values(first_two.union(&last_two)));
+        assert!(first_two.intersect(&last_two).is_empty());
+        assert_expected_eq_actual!(vec![7, 7], values(first_two.difference(&last_two)));
+    }
+
+    #[test]
+    fn entries_of_different_dbs_are_distinct() {
+        let (x, y) = (DB::new(vec![1, 2, 3]), DB::new(vec![1, 2, 3]));
+        let (xs, ys) = (x.as_view(), y.as_view());
+        assert_expected_eq_actual!(vec![1, 2, 3, 1, 2, 3], values(xs.union(&ys)));
+        assert!(xs.intersect(&ys).is_empty());
+        assert_expected_eq_actual!(vec![1, 2, 3], values(xs.difference(&ys)));
+    }
+}
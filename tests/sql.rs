@@ -0,0 +1,175 @@
+extern crate wk3;
+
+pub use wk3::{DB, Value};
+
+// This macro is an assertion with nicely formatted failure output
+macro_rules! assert_expected_eq_actual {
+    ($a:expr, $b:expr) => ({
+        let (a, b) = (&$a, &$b);
+        assert!(*a == *b,
+                "\nExpected `{:?}` is not equal to Actual `{:?}`\nAssertion: `assert_expected_eq_actual!({}, {})`",
+                *a,
+                *b,
+                stringify!($a),
+                stringify!($b));
+    })
+}
+
+mod sql {
+    use super::{DB, Value};
+    use wk3::sql::{self, CompareOp, Expr, Operand, TokenKind};
+    use wk3::FieldAccess;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Person {
+        name: String,
+        age: i64,
+        score: Option<f64>,
+    }
+
+    impl FieldAccess for Person {
+        fn get(&self, name: &str) -> Option<Value> {
+            match name {
+                "name" => Some(self.name.clone().into()),
+                "age" => Some(self.age.into()),
+                "score" => Some(self.score.into()),
+                _ => None,
+            }
+        }
+    }
+
+    fn person(name: &str, age: i64, score: Option<f64>) -> Person {
+        Person { name: name.to_string(), age, score }
+    }
+
+    fn db() -> DB<Person> {
+        DB::new(vec![person("Alice", 34, Some(9.5)),
+                     person("Bob", 25, None),
+                     person("Anna", 41, Some(7.0)),
+                     person("Dave", 30, Some(8.25)),
+                     person("Al", 52, None)])
+    }
+
+    fn names<'a>(view: wk3::DBView<'a, Person>) -> Vec<&'a str> {
+        view.into_iter().map(|p| &p.name[..]).collect()
+    }
+
+    fn run<'a>(x: &'a DB<Person>, query: &str) -> Vec<&'a str> {
+        names(x.sql(query).unwrap_or_else(|e| panic!("{}", e)))
+    }
+
+    #[test]
+    fn tokenize() {
+        let tokens = sql::tokenize("age >= 3.5\n  AND name<>'it''s'").unwrap();
+        let kinds: Vec<TokenKind> = tokens.iter().map(|t| t.kind.clone()).collect();
+        assert_expected_eq_actual!(vec![TokenKind::Word("age".to_string()),
+                                        TokenKind::Compare(CompareOp::GtEq),
+                                        TokenKind::Float(3.5),
+                                        TokenKind::Word("AND".to_string()),
+                                        TokenKind::Word("name".to_string()),
+                                        TokenKind::Compare(CompareOp::NotEq),
+                                        TokenKind::Str("it's".to_string()),
+                                        TokenKind::End],
+                                   kinds);
+        let positions: Vec<(usize, usize)> = tokens.iter().map(|t| (t.line, t.column)).collect();
+        assert_expected_eq_actual!(vec![(1, 1), (1, 5), (1, 8), (2, 3), (2, 7), (2, 11), (2, 13),
+                                        (2, 20)],
+                                   positions);
+    }
+
+    #[test]
+    fn parse() {
+        let select = sql::parse("select * where not age < -3 order by age desc, name limit 5")
+            .unwrap();
+        let compare = Expr::Compare(Operand::Field("age".to_string()),
+                                    CompareOp::Lt,
+                                    Operand::Literal(Value::Int(-3)));
+        assert_expected_eq_actual!(Some(Expr::Not(Box::new(compare))), select.filter);
+        let order: Vec<(&str, bool)> =
+            select.order_by.iter().map(|k| (&k.field[..], k.descending)).collect();
+        assert_expected_eq_actual!(vec![("age", true), ("name", false)], order);
+        assert_expected_eq_actual!((Some(5), None), (select.limit, select.offset));
+    }
+
+    #[test]
+    fn precedence() {
+        let select = sql::parse("SELECT * WHERE a = 1 OR b = 2 AND NOT (c = 3 OR d IS NULL)")
+            .unwrap();
+        assert_expected_eq_actual!("(a = 1 OR (b = 2 AND NOT (c = 3 OR d IS NULL)))",
+                                   select.filter.unwrap().to_string());
+    }
+
+    #[test]
+    fn where_clause() {
+        let x = db();
+        assert_expected_eq_actual!(vec!["Alice", "Anna", "Al"],
+                                   run(&x, "SELECT * WHERE age > 30 AND name LIKE 'A%'"));
+        assert_expected_eq_actual!(vec!["Alice", "Anna", "Dave"],
+                                   run(&x, "SELECT * WHERE score >= 7"));
+        assert_expected_eq_actual!(vec!["Bob", "Al"], run(&x, "SELECT * WHERE score IS NULL"));
+        assert_expected_eq_actual!(vec!["Bob", "Dave"],
+                                   run(&x, "SELECT * WHERE name NOT LIKE 'A%'"));
+        assert_expected_eq_actual!(vec!["Anna"], run(&x, "SELECT * WHERE name LIKE '_n%a'"));
+        assert_expected_eq_actual!(vec!["Dave"], run(&x, "SELECT * WHERE score = 8.25"));
+    }
+
+    #[test]
+    fn null_and_mismatched_types_never_compare() {
+        let x = db();
+        assert!(run(&x, "SELECT * WHERE score = NULL").is_empty());
+        assert!(run(&x, "SELECT * WHERE name > 3").is_empty());
+        assert!(run(&x, "SELECT * WHERE height < 3").is_empty());
+        assert_expected_eq_actual!(vec!["Bob", "Al"], run(&x, "SELECT * WHERE NOT score > 0"));
+    }
+
+    #[test]
+    fn order_limit_offset() {
+        let x = db();
+        assert_expected_eq_actual!(vec!["Al", "Anna", "Alice"],
+                                   run(&x, "SELECT * WHERE name LIKE 'A%' ORDER BY age DESC"));
+        assert_expected_eq_actual!(vec!["Dave", "Alice"],
+                                   run(&x, "SELECT * ORDER BY age LIMIT 2 OFFSET 1"));
+        assert_expected_eq_actual!(vec!["Bob", "Al", "Anna", "Dave", "Alice"],
+                                   run(&x, "SELECT * ORDER BY score, age"));
+    }
+
+    #[test]
+    fn query_can_be_explained() {
+        let x = db();
+        let select = sql::parse("SELECT * WHERE age > 30 LIMIT 2").unwrap();
+        assert_expected_eq_actual!("Limit 2  [estimated rows: 2]\n  \
+                                    -> Filter (age > 30)  [estimated rows: 5]\n    \
+                                    -> Scan  [estimated rows: 5]",
+                                   select.query(&x).explain().to_string());
+    }
+
+    fn error(query: &str) -> (String, usize, usize) {
+        let e = sql::parse(query).unwrap_err();
+        (e.message().to_string(), e.line(), e.column())
+    }
+
+    #[test]
+    fn errors() {
+        assert_expected_eq_actual!(("expected `SELECT`, found `DELETE`".to_string(), 1, 1),
+                                   error("DELETE *"));
+        assert_expected_eq_actual!(("expected a comparison, `LIKE` or `IS`, found `30`"
+                                        .to_string(),
+                                    2,
+                                    11),
+                                   error("SELECT *\nWHERE age 30"));
+        assert_expected_eq_actual!(("unterminated string".to_string(), 1, 26),
+                                   error("SELECT * WHERE name LIKE 'A%"));
+        assert_expected_eq_actual!(("unexpected character `;`".to_string(), 1, 9),
+                                   error("SELECT *;"));
+        assert_expected_eq_actual!(("expected a field name, found `limit`".to_string(), 1, 19),
+                                   error("SELECT * ORDER BY limit"));
+        assert_expected_eq_actual!(("expected `)`, found end of query".to_string(), 1, 27),
+                                   error("SELECT * WHERE (age > 30  "));
+        assert_expected_eq_actual!(("expected end of query, found `WHERE`".to_string(), 1, 18),
+                                   error("SELECT * LIMIT 1 WHERE age > 3"));
+        let e = sql::parse("SELECT * WHERE").unwrap_err();
+        assert_expected_eq_actual!("expected a field name or value, found end of query at line 1, \
+                                    column 15",
+                                   e.to_string());
+    }
+}
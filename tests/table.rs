@@ -0,0 +1,138 @@
+extern crate wk3;
+
+pub use wk3::{Field, FieldType, Row, Schema, Table, TypeError, Value};
+
+// This macro is an assertion with nicely formatted failure output
+macro_rules! assert_expected_eq_actual {
+    ($a:expr, $b:expr) => ({
+        let (a, b) = (&$a, &$b);
+        assert!(*a == *b,
+                "\nExpected `{:?}` is not equal to Actual `{:?}`\nAssertion: `assert_expected_eq_actual!({}, {})`",
+                *a,
+                *b,
+                stringify!($a),
+                stringify!($b));
+    })
+}
+
+mod table {
+    use super::{Field, FieldType, Row, Schema, Table, TypeError, Value};
+
+    fn schema() -> Schema {
+        Schema::new(vec![Field::of::<String>("name"),
+                         Field::of::<i64>("age"),
+                         Field::of::<Option<Vec<u8>>>("avatar"),
+                         Field::of::<Vec<Value>>("tags")])
+    }
+
+    fn table() -> Table {
+        let mut t = Table::new(schema());
+        t.insert(vec!["alice".into(), Value::Int(30), Value::Null, Value::List(vec![])]).unwrap();
+        t.insert(vec!["bob".into(),
+                      Value::Int(25),
+                      Value::Bytes(vec![0, 255]),
+                      Value::List(vec!["admin".into()])])
+            .unwrap();
+        t.insert(vec!["carol".into(), Value::Int(41), Value::Null, Value::List(vec![])]).unwrap();
+        t
+    }
+
+    fn name(row: &Row) -> &Value {
+        row.get("name").unwrap()
+    }
+
+    #[test]
+    fn insert_checks_types() {
+        let mut t = table();
+        assert_expected_eq_actual!(Err(TypeError::WrongLength { expected: 4, found: 1 }),
+                                   t.insert(vec!["dave".into()]));
+        let wrong = vec!["dave".into(), Value::Float(1.0), Value::Null, Value::List(vec![])];
+        assert_expected_eq_actual!(Err(TypeError::WrongType {
+                                       field: "age".to_string(),
+                                       expected: FieldType::Int,
+                                       found: Some(FieldType::Float),
+                                   }),
+                                   t.insert(wrong));
+        let null = vec![Value::Null, Value::Int(1), Value::Null, Value::List(vec![])];
+        let e = t.insert(null).unwrap_err();
+        assert_expected_eq_actual!("field `name` is not nullable, found NULL", e.to_string());
+        assert_expected_eq_actual!(3, t.len());
+    }
+
+    #[test]
+    fn select_and_query() {
+        let t = table();
+        let view = t.select_where(|r| r.get("age") != Some(&Value::Int(25)));
+        let names: Vec<Value> = view.iter().map(name).cloned().collect();
+        assert_expected_eq_actual!(vec![Value::from("alice"), Value::from("carol")], names);
+        let view = t.sql("SELECT * WHERE avatar IS NOT NULL").unwrap();
+        let names: Vec<Value> = view.iter().map(name).cloned().collect();
+        assert_expected_eq_actual!(vec![Value::from("bob")], names);
+        let view = t.sql("SELECT * WHERE avatar = NULL ORDER BY tags DESC").unwrap();
+        assert!(view.is_empty());
+    }
+
+    #[test]
+    fn edits_are_checked() {
+        let mut t = table();
+        {
+            let mut view = t.select_where_mut(|r| r.get("avatar") == Some(&Value::Null));
+            for mut row in view.iter_mut() {
+                assert_expected_eq_actual!(Ok(Value::Null), row.set("avatar", vec![1u8]));
+                assert!(row.set("age", "old").is_err());
+                assert!(row.set("height", 1i64).is_err());
+            }
+        }
+        let avatars: Vec<Value> = t.iter().map(|r| r.get("avatar").unwrap().clone()).collect();
+        assert_expected_eq_actual!(vec![Value::Bytes(vec![1]),
+                                        Value::Bytes(vec![0, 255]),
+                                        Value::Bytes(vec![1])],
+                                   avatars);
+        assert_expected_eq_actual!(Value::Int(30), t.iter().next().unwrap().values()[1]);
+    }
+
+    #[test]
+    fn rows_lent_out_mutably() {
+        let mut t = table();
+        let bob = t.select_where(|r| r.get("name") == Some(&Value::from("bob")));
+        let bob = bob.rows().next().unwrap().0;
+        {
+            let mut row = t.get_mut(bob).unwrap();
+            assert_expected_eq_actual!(Some(&Value::Int(25)), row.get("age"));
+            row.set("age", 26i64).unwrap();
+        }
+        for mut row in t.iter_mut() {
+            assert!(row.set("name", Value::Null).is_err());
+        }
+        let view = t.as_view_mut().select_where_mut(|r| r.get("age") == Some(&Value::Int(26)));
+        for mut row in view {
+            row.set("tags", Value::List(vec!["admin".into(), "ops".into()])).unwrap();
+        }
+        let bob = t.get(bob).unwrap();
+        assert_expected_eq_actual!(t.schema(), bob.schema());
+        assert_expected_eq_actual!(Some(&Value::Int(26)), bob.get("age"));
+        assert_expected_eq_actual!(Some(&Value::List(vec!["admin".into(), "ops".into()])),
+                                   bob.get("tags"));
+    }
+
+    #[test]
+    fn remove_and_indexes() {
+        let mut t = table();
+        t.create_index("tag_count", |r: &Row| match r.get("tags") {
+            Some(Value::List(tags)) => tags.len(),
+            _ => 0,
+        });
+        assert_expected_eq_actual!(1, t.select_eq("tag_count", &1usize).len());
+        let removed = t.remove_where(|r| r.get("name") == Some(&Value::from("bob")));
+        assert_expected_eq_actual!(1, removed.len());
+        assert!(t.select_eq("tag_count", &1usize).is_empty());
+    }
+
+    #[test]
+    fn display() {
+        assert_expected_eq_actual!("X'00ff'", Value::Bytes(vec![0, 255]).to_string());
+        assert_expected_eq_actual!("[1, 'a', NULL]",
+                                   Value::List(vec![1i64.into(), "a".into(), Value::Null])
+                                       .to_string());
+    }
+}
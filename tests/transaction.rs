@@ -0,0 +1,140 @@
+extern crate wk3;
+
+pub use wk3::DB;
+
+// This macro is an assertion with nicely formatted failure output
+macro_rules! assert_expected_eq_actual {
+    ($a:expr, $b:expr) => ({
+        let (a, b) = (&$a, &$b);
+        assert!(*a == *b,
+                "\nExpected `{:?}` is not equal to Actual `{:?}`\nAssertion: `assert_expected_eq_actual!({}, {})`",
+                *a,
+                *b,
+                stringify!($a),
+                stringify!($b));
+    })
+}
+
+mod transaction {
+    use super::DB;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    struct NoCopy(i32);
+
+    fn db() -> DB<NoCopy> {
+        DB::new(vec![NoCopy(1), NoCopy(2), NoCopy(3)])
+    }
+
+    fn values(x: &DB<NoCopy>) -> Vec<i32> {
+        x.iter().map(|n| n.0).collect()
+    }
+
+    #[test]
+    fn commit() {
+        let mut x = db();
+        let result: Result<_, ()> = x.transaction(|tx| {
+            let id = tx.insert(NoCopy(4));
+            tx.remove_where(|n| n.0 == 1);
+            for n in tx.as_view_mut() {
+                n.0 *= 10;
+            }
+            assert_expected_eq_actual!(vec![20, 30, 40], values(tx));
+            Ok(id)
+        });
+        let id = result.unwrap();
+        assert_expected_eq_actual!(vec![20, 30, 40], values(&x));
+        assert_expected_eq_actual!(Some(&NoCopy(40)), x.get(id));
+    }
+
+    #[test]
+    fn rollback_on_err() {
+        let mut x = db();
+        let before = x.as_view().rows().map(|(id, n)| (id, n.clone())).collect::<Vec<_>>();
+        let result: Result<(), &str> = x.transaction(|tx| {
+            tx.insert(NoCopy(4));
+            let first = tx.as_view().rows().next().unwrap().0;
+            tx.get_mut(first).unwrap().0 = 100;
+            tx.remove(first);
+            tx.retain(|n| n.0 != 2);
+            for n in tx.iter_mut() {
+                n.0 = -n.0;
+            }
+            Err("failed")
+        });
+        assert_expected_eq_actual!(Err("failed"), result);
+        let after = x.as_view().rows().map(|(id, n)| (id, n.clone())).collect::<Vec<_>>();
+        assert_expected_eq_actual!(before, after);
+    }
+
+    #[test]
+    fn rollback_on_panic() {
+        let mut x = db();
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            let _: Result<(), ()> = x.transaction(|tx| {
+                tx.insert(NoCopy(4));
+                tx.select_where_mut(|n| n.0 > 1).into_iter().for_each(|n| n.0 = 0);
+                panic!("midway");
+            });
+        }));
+        assert!(result.is_err());
+        assert_expected_eq_actual!(vec![1, 2, 3], values(&x));
+    }
+
+    #[test]
+    fn savepoints() {
+        let mut x = db();
+        let result: Result<(), ()> = x.transaction(|tx| {
+            tx.insert(NoCopy(4));
+            let inner: Result<(), ()> = tx.savepoint(|tx| {
+                tx.insert(NoCopy(5));
+                let nested: Result<(), ()> = tx.savepoint(|tx| {
+                    tx.insert(NoCopy(6));
+                    Ok(())
+                });
+                assert!(nested.is_ok());
+                tx.remove_where(|n| n.0 < 3);
+                Err(())
+            });
+            assert!(inner.is_err());
+            assert_expected_eq_actual!(vec![1, 2, 3, 4], values(tx));
+            tx.savepoint(|tx| {
+                tx.insert(NoCopy(7));
+                Ok(())
+            })
+        });
+        assert!(result.is_ok());
+        assert_expected_eq_actual!(vec![1, 2, 3, 4, 7], values(&x));
+    }
+
+    #[test]
+    fn committed_savepoint_undone_with_transaction() {
+        let mut x = db();
+        let result: Result<(), ()> = x.transaction(|tx| {
+            tx.savepoint(|tx| -> Result<(), ()> {
+                tx.insert(NoCopy(4));
+                Ok(())
+            })?;
+            Err(())
+        });
+        assert!(result.is_err());
+        assert_expected_eq_actual!(vec![1, 2, 3], values(&x));
+    }
+
+    #[test]
+    fn indexes_rolled_back() {
+        let mut x = db();
+        x.create_index("value", |n: &NoCopy| n.0);
+        let _: Result<(), ()> = x.transaction(|tx| {
+            for n in tx.as_view_mut() {
+                n.0 = 9;
+            }
+            tx.remove_where(|n| n.0 == 9);
+            tx.insert(NoCopy(2));
+            Err(())
+        });
+        assert_expected_eq_actual!(1, x.select_eq("value", &2).len());
+        assert!(x.select_eq("value", &9).is_empty());
+        assert_expected_eq_actual!(vec![1, 2, 3], values(&x));
+    }
+}
@@ -0,0 +1,101 @@
+extern crate wk3;
+
+pub use wk3::{Change, DB};
+
+// This macro is an assertion with nicely formatted failure output
+macro_rules! assert_expected_eq_actual {
+    ($a:expr, $b:expr) => ({
+        let (a, b) = (&$a, &$b);
+        assert!(*a == *b,
+                "\nExpected `{:?}` is not equal to Actual `{:?}`\nAssertion: `assert_expected_eq_actual!({}, {})`",
+                *a,
+                *b,
+                stringify!($a),
+                stringify!($b));
+    })
+}
+
+mod update {
+    use super::{Change, DB};
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    struct NoCopy(i32);
+
+    #[derive(Debug)]
+    struct NoCompare(i32);
+
+    fn db() -> DB<NoCopy> {
+        DB::new(vec![NoCopy(1), NoCopy(-2), NoCopy(3), NoCopy(-4)])
+    }
+
+    fn values(x: &DB<NoCopy>) -> Vec<i32> {
+        x.iter().map(|n| n.0).collect()
+    }
+
+    #[test]
+    fn update() {
+        let mut x = db();
+        let ids: Vec<_> = x.as_view().rows().map(|(id, _)| id).collect();
+        let report = x.as_view_mut().update_checked(|n| n.0 = n.0.abs());
+        assert_expected_eq_actual!(ids, report.affected());
+        assert_expected_eq_actual!(Some(&[ids[1], ids[3]][..]), report.modified());
+        assert_expected_eq_actual!(Some(2), report.modified_count());
+        assert_expected_eq_actual!(None, report.diff());
+        assert_expected_eq_actual!(vec![1, 2, 3, 4], values(&x));
+    }
+
+    #[test]
+    fn update_where() {
+        let mut x = db();
+        let report = {
+            let mut view = x.select_where_mut(|n| n.0 != 3);
+            view.update_where_checked(|n| n.0 < 0, |n| n.0 *= 10)
+        };
+        assert_expected_eq_actual!(2, report.affected().len());
+        assert_expected_eq_actual!(Some(2), report.modified_count());
+        assert_expected_eq_actual!(vec![1, -20, 3, -40], values(&x));
+    }
+
+    #[test]
+    fn update_without_comparing() {
+        let mut x = DB::new(vec![NoCompare(1), NoCompare(-2), NoCompare(3)]);
+        let ids: Vec<_> = x.as_view().rows().map(|(id, _)| id).collect();
+        let report = x.as_view_mut().update_where(|n| n.0 > 0, |n| n.0 += 1);
+        assert_expected_eq_actual!(vec![ids[0], ids[2]], report.affected());
+        assert_expected_eq_actual!(None, report.modified_count());
+        assert_expected_eq_actual!(vec![2, -2, 4], x.iter().map(|n| n.0).collect::<Vec<_>>());
+        let report = x.as_view_mut().update(|n| n.0 = 0);
+        assert_expected_eq_actual!(ids, report.affected());
+    }
+
+    #[test]
+    fn diff() {
+        let mut x = db();
+        let id = x.as_view().rows().nth(2).unwrap().0;
+        let report = x.as_view_mut().update_where_with_diff(|n| n.0 > 0, |n| {
+            if n.0 == 3 {
+                n.0 = 30;
+            }
+        });
+        assert_expected_eq_actual!(2, report.affected().len());
+        assert_expected_eq_actual!(Some(vec![Change { id, before: NoCopy(3), after: NoCopy(30) }]),
+                                   report.into_diff());
+    }
+
+    #[test]
+    fn unchanged_entries_are_not_modified() {
+        let mut x = db();
+        let report = x.as_view_mut().update_with_diff(|n| n.0 += 0);
+        assert_expected_eq_actual!(4, report.affected().len());
+        assert_expected_eq_actual!(Some(&[][..]), report.modified());
+        assert_expected_eq_actual!(Some(&[][..]), report.diff());
+    }
+
+    #[test]
+    fn indexes_see_updates() {
+        let mut x = db();
+        x.create_index("sign", |n: &NoCopy| n.0 > 0);
+        x.as_view_mut().update(|n| n.0 = n.0.abs());
+        assert_expected_eq_actual!(4, x.select_eq("sign", &true).len());
+    }
+}
@@ -0,0 +1,318 @@
+#[macro_use]
+extern crate wk3;
+
+pub use wk3::{BinaryError, DB, WalError};
+// This macro is an assertion with nicely formatted failure output
+macro_rules! assert_expected_eq_actual {
+    ($a:expr, $b:expr) => ({
+        let (a, b) = (&$a, &$b);
+        assert!(*a == *b,
+                "\nExpected `{:?}` is not equal to Actual `{:?}`\nAssertion: `assert_expected_eq_actual!({}, {})`",
+                *a,
+                *b,
+                stringify!($a),
+                stringify!($b));
+    })
+}
+
+mod wal {
+    use super::{BinaryError, DB, WalError};
+    use std::env;
+    use std::fs::{self, OpenOptions};
+    use std::path::{Path, PathBuf};
+    use std::process;
+
+    record! {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Person {
+            name: String,
+            age: u32,
+        }
+    }
+
+    record! {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Other {
+            id: i64,
+        }
+    }
+
+    fn person(name: &str, age: u32) -> Person {
+        Person { name: name.to_string(), age }
+    }
+
+    /// A fresh directory for one test to keep a DB in
+    fn dir(test: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("wk3-wal-{}-{}", process::id(), test));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn log_len(dir: &Path) -> u64 {
+        fs::metadata(dir.join("log.wal")).unwrap().len()
+    }
+
+    /// Makes a durable DB and changes it in every way, returning it as it ends up
+    fn changed(dir: &Path) -> DB<Person> {
+        let mut x = DB::new(vec![person("alice", 30), person("bob", 25)]);
+        x.make_durable(dir).unwrap();
+        let carol = x.insert(person("carol", 41));
+        x.insert(person("dan", 19));
+        let first = x.as_view().rows().next().unwrap().0;
+        x.remove(first);
+        x.get_mut(carol).unwrap().age = 42;
+        for p in x.select_where_mut(|p| p.age < 20) {
+            p.name.push_str(" jr");
+        }
+        x.remove_where(|p| p.name == "bob");
+        x.insert(person("erin", 22));
+        x
+    }
+
+    #[test]
+    fn recover() {
+        let dir = dir("recover");
+        let x = changed(&dir);
+        let y: DB<Person> = DB::recover(&dir).unwrap();
+        assert_expected_eq_actual!(x, y);
+        assert!(y.is_durable());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn recovered_db_stays_durable() {
+        let dir = dir("stays_durable");
+        let mut x = changed(&dir);
+        let mut y: DB<Person> = DB::recover(&dir).unwrap();
+        assert_expected_eq_actual!(x.insert(person("frank", 60)), y.insert(person("frank", 60)));
+        drop(y);
+        let z: DB<Person> = DB::recover(&dir).unwrap();
+        assert_expected_eq_actual!(x, z);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn edits_are_logged_once_finished() {
+        let dir = dir("edits");
+        let mut x = DB::new(vec![person("alice", 30)]);
+        x.make_durable(&dir).unwrap();
+        for p in x.as_view_mut() {
+            p.age += 1;
+        }
+        let before = log_len(&dir);
+        x.flush_edits();
+        assert!(log_len(&dir) > before);
+        let y: DB<Person> = DB::recover(&dir).unwrap();
+        assert_expected_eq_actual!(31, y.iter().next().unwrap().age);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn torn_final_record() {
+        let dir = dir("torn");
+        let mut x = changed(&dir);
+        let expected: Vec<Person> = x.iter().cloned().collect();
+        let whole = log_len(&dir);
+        x.insert(person("torn", 1));
+        let torn = log_len(&dir) - 3;
+        OpenOptions::new().write(true).open(dir.join("log.wal")).unwrap().set_len(torn).unwrap();
+
+        let mut y: DB<Person> = DB::recover(&dir).unwrap();
+        assert_expected_eq_actual!(expected, y.iter().cloned().collect::<Vec<_>>());
+        // The torn record is cut off, so later records follow on from the whole ones
+        assert_expected_eq_actual!(whole, log_len(&dir));
+        let id = y.insert(person("after", 2));
+        let z: DB<Person> = DB::recover(&dir).unwrap();
+        assert_expected_eq_actual!(Some(&person("after", 2)), z.get(id));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn damaged_final_record() {
+        let dir = dir("damaged_final");
+        let mut x = DB::new(vec![person("alice", 30)]);
+        x.make_durable(&dir).unwrap();
+        x.insert(person("bob", 25));
+        let mut log = fs::read(dir.join("log.wal")).unwrap();
+        let last = log.len() - 1;
+        log[last] ^= 0xff;
+        fs::write(dir.join("log.wal"), &log).unwrap();
+        // It's all there, so it wasn't cut short, and is left for someone to look at
+        match DB::<Person>::recover(&dir) {
+            Err(WalError::CorruptRecord(14)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert_expected_eq_actual!(log, fs::read(dir.join("log.wal")).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn damaged_length() {
+        let dir = dir("damaged_length");
+        let mut x = DB::new(vec![person("alice", 30)]);
+        x.make_durable(&dir).unwrap();
+        for i in 0..3 {
+            x.insert(person("bob", i));
+        }
+        let mut log = fs::read(dir.join("log.wal")).unwrap();
+        // Make the first record claim to run past the end of the log
+        log[14 + 1] = 0xff;
+        fs::write(dir.join("log.wal"), &log).unwrap();
+        match DB::<Person>::recover(&dir) {
+            Err(WalError::CorruptRecord(14)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert_expected_eq_actual!(log, fs::read(dir.join("log.wal")).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn corrupt_record() {
+        let dir = dir("corrupt");
+        let mut x = DB::new(Vec::new());
+        x.make_durable(&dir).unwrap();
+        x.insert(person("alice", 30));
+        let second = log_len(&dir);
+        x.insert(person("bob", 25));
+        let mut log = fs::read(dir.join("log.wal")).unwrap();
+        // Damage the first record, which is followed by another
+        log[second as usize - 1] ^= 0xff;
+        fs::write(dir.join("log.wal"), &log).unwrap();
+        match DB::<Person>::recover(&dir) {
+            Err(WalError::CorruptRecord(14)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn checkpoint() {
+        let dir = dir("checkpoint");
+        let mut x = changed(&dir);
+        x.checkpoint().unwrap();
+        assert_expected_eq_actual!(14, log_len(&dir));
+        let id = x.insert(person("frank", 60));
+        let y: DB<Person> = DB::recover(&dir).unwrap();
+        assert_expected_eq_actual!(x, y);
+        assert!(y.contains(id));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn interrupted_checkpoint() {
+        let dir = dir("interrupted");
+        let mut x = changed(&dir);
+        let log = fs::read(dir.join("log.wal")).unwrap();
+        x.checkpoint().unwrap();
+        // As if the process stopped after saving the snapshot but before emptying the log
+        fs::write(dir.join("log.wal"), &log).unwrap();
+        let y: DB<Person> = DB::recover(&dir).unwrap();
+        assert_expected_eq_actual!(x, y);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn transactions() {
+        let dir = dir("transactions");
+        let mut x = DB::new(vec![person("alice", 30)]);
+        x.make_durable(&dir).unwrap();
+        let committed: Result<(), ()> = x.transaction(|tx| {
+            tx.insert(person("bob", 25));
+            Ok(())
+        });
+        let before = log_len(&dir);
+        let failed: Result<(), ()> = x.transaction(|tx| {
+            let first = tx.as_view().rows().next().unwrap().0;
+            tx.get_mut(first).unwrap().age = 99;
+            tx.remove(first);
+            tx.insert(person("carol", 41));
+            Err(())
+        });
+        assert!(committed.is_ok() && failed.is_err());
+        // A transaction which is rolled back logs nothing
+        assert_expected_eq_actual!(before, log_len(&dir));
+        let y: DB<Person> = DB::recover(&dir).unwrap();
+        assert_expected_eq_actual!(x, y);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn torn_transaction() {
+        let dir = dir("torn_transaction");
+        let mut x = DB::new(vec![person("alice", 30), person("bob", 25)]);
+        x.make_durable(&dir).unwrap();
+        let expected: Vec<Person> = x.iter().cloned().collect();
+        let whole = log_len(&dir);
+        let committed: Result<(), ()> = x.transaction(|tx| {
+            tx.insert(person("carol", 41));
+            tx.remove_where(|p| p.name == "alice");
+            for p in tx.as_view_mut() {
+                p.age += 1;
+            }
+            Ok(())
+        });
+        assert!(committed.is_ok());
+        let end = log_len(&dir);
+        drop(x);
+        let log = fs::read(dir.join("log.wal")).unwrap();
+        // Cut the log off just before the commit record, and part way through the records
+        // before it. Either way, none of the transaction is recovered.
+        for &len in &[end - 13, end - 20, whole + 13] {
+            fs::write(dir.join("log.wal"), &log[..len as usize]).unwrap();
+            let mut y: DB<Person> = DB::recover(&dir).unwrap();
+            assert_expected_eq_actual!(expected, y.iter().cloned().collect::<Vec<_>>());
+            assert_expected_eq_actual!(whole, log_len(&dir));
+            let id = y.insert(person("after", 2));
+            let z: DB<Person> = DB::recover(&dir).unwrap();
+            assert_expected_eq_actual!(Some(&person("after", 2)), z.get(id));
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn edits_are_logged_on_drop() {
+        let dir = dir("edits_on_drop");
+        let mut x = DB::new(vec![person("alice", 30), person("bob", 25)]);
+        x.make_durable(&dir).unwrap();
+        for p in x.select_where_mut(|p| p.name == "bob") {
+            p.age = 26;
+        }
+        drop(x);
+        let y: DB<Person> = DB::recover(&dir).unwrap();
+        assert_expected_eq_actual!(vec![30, 26], y.iter().map(|p| p.age).collect::<Vec<_>>());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn wrong_type() {
+        let dir = dir("wrong_type");
+        changed(&dir);
+        match DB::<Other>::recover(&dir) {
+            Err(WalError::Snapshot(BinaryError::SchemaMismatch { .. })) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn missing() {
+        match DB::<Person>::recover(dir("missing")) {
+            Err(WalError::Io(_)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn not_durable() {
+        let mut x = DB::new(vec![person("alice", 30)]);
+        assert!(!x.is_durable());
+        assert!(x.checkpoint().is_err());
+        let dir = dir("not_durable");
+        x.make_durable(&dir).unwrap();
+        x.stop_durability();
+        x.insert(person("bob", 25));
+        let y: DB<Person> = DB::recover(&dir).unwrap();
+        assert_expected_eq_actual!(1, y.len());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}